@@ -0,0 +1,108 @@
+//! Compares the throughput of each [`ReadStrategy`] on one large file, and of BLAKE3 on one
+//! core against every core.
+//!
+//! ```sh
+//! cargo bench --bench read_strategies --features mmap
+//! HASH_BENCH_FILE=/path/to/big.iso cargo bench --bench read_strategies --features mmap
+//! ```
+//!
+//! Without `HASH_BENCH_FILE`, a file of `HASH_BENCH_MIB` MiB (default 512) is written to the
+//! temp directory first. It will mostly be served from the page cache, which measures the cost
+//! of reading and hashing rather than of the disk.
+
+use hash_checker::{HashAlgorithm, HashOptions, ReadStrategy};
+use sha2::Digest as _;
+use std::io::{BufReader, Write as _};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// How many times each strategy is run. The fastest run is reported.
+const RUNS: usize = 5;
+
+fn main() {
+    let (path, temporary) = match std::env::var_os("HASH_BENCH_FILE") {
+        Some(path) => (PathBuf::from(path), false),
+        None => (write_test_file(), true),
+    };
+    let len = std::fs::metadata(&path)
+        .expect("Failed to stat the test file")
+        .len();
+    println!(
+        "Hashing {} ({} MiB) with SHA-256",
+        path.display(),
+        len / (1024 * 1024)
+    );
+
+    report("BufReader + io::copy", len, || {
+        let file = std::fs::File::open(&path).expect("Failed to open the test file");
+        let mut hasher = sha2::Sha256::new();
+        std::io::copy(&mut BufReader::new(file), &mut hasher)
+            .expect("Failed to read the test file");
+        hasher.finalize();
+    });
+    for strategy in ReadStrategy::ALL {
+        report(strategy.id(), len, || {
+            let options = HashOptions::new().read_strategy(strategy);
+            hash_checker::hash_file_with(&path, &[HashAlgorithm::Sha256], options)
+                .expect("Failed to hash the test file");
+        });
+    }
+    if cfg!(not(feature = "mmap")) {
+        println!("(mmap falls back to large reads; run with --features mmap to map the file)");
+    }
+
+    println!("Hashing with BLAKE3");
+    for (name, parallel) in [("one core", false), ("every core", true)] {
+        report(name, len, || {
+            let mut options = HashOptions::new().read_strategy(ReadStrategy::Mmap);
+            if parallel {
+                options = options.parallel_blake3(0);
+            }
+            hash_checker::hash_file_with(&path, &[HashAlgorithm::Blake3], options)
+                .expect("Failed to hash the test file");
+        });
+    }
+
+    if temporary {
+        std::fs::remove_file(&path).ok();
+    }
+}
+
+fn write_test_file() -> PathBuf {
+    let mib: usize = std::env::var("HASH_BENCH_MIB")
+        .ok()
+        .and_then(|mib| mib.parse().ok())
+        .unwrap_or(512);
+    let path = std::env::temp_dir().join(format!("hash-checker-bench-{}", std::process::id()));
+    let mut file = std::fs::File::create(&path).expect("Failed to create the test file");
+
+    // Cheap pseudo-random bytes, so nothing along the way can shortcut runs of zeroes.
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let chunk: Vec<u8> = (0..1024 * 1024)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state.to_le_bytes()[0]
+        })
+        .collect();
+    for _ in 0..mib {
+        file.write_all(&chunk)
+            .expect("Failed to write the test file");
+    }
+    file.sync_all().ok();
+    path
+}
+
+fn report(name: &str, len: u64, mut run: impl FnMut()) {
+    let fastest = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or(Duration::MAX);
+    let throughput = len as f64 / fastest.as_secs_f64() / (1024.0 * 1024.0);
+    println!("{name:>22}: {fastest:>10.2?} {throughput:>8.0} MiB/s");
+}
@@ -1,25 +1,308 @@
+use crate::file_dialog::FileDialogs;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{ChecksumEntry, ChecksumFile, Signer, TrustedKeys};
+use crate::{
+    Comparison, Digest, ExpectedDigest, HashAlgorithm, Integrity, JobEvent, JobId, JobSource, Jobs,
+    Manifest, ManifestFormat, Progress,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
-pub struct TemplateApp {
-    // Example stuff:
-    label: String,
+pub struct HashCheckerApp {
+    /// The path typed into the file field.
+    path: String,
+
+    /// The algorithms every file is hashed with.
+    algorithms: BTreeSet<HashAlgorithm>,
+
+    /// Whether large files are hashed with BLAKE3 on every core.
+    parallel_blake3: bool,
+
+    /// Files and folders recently opened through the file dialogs, most recent first.
+    recent: Vec<PathBuf>,
 
+    /// The digest the user expects, pasted from e.g. a vendor's download page.
     #[serde(skip)] // This how you opt-out of serialization of a field
-    value: f32,
+    expected: String,
+
+    #[serde(skip)]
+    entries: Vec<Entry>,
+
+    #[serde(skip)]
+    jobs: Jobs,
+
+    #[serde(skip)]
+    dialogs: FileDialogs,
+
+    /// Why files the user dropped weren't hashed, until something else is queued.
+    #[serde(skip)]
+    drop_notice: Option<String>,
+
+    /// A checksum file opened to check hashed files against, or why it couldn't be read.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    checksums: Option<Result<SignedChecksums, String>>,
+
+    /// The outcome of the last attempt to trust a public key.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    key_import: Option<Result<String, String>>,
 }
 
-impl Default for TemplateApp {
+impl Default for HashCheckerApp {
     fn default() -> Self {
         Self {
-            // Example stuff:
-            label: "Hello World!".to_owned(),
-            value: 2.7,
+            path: String::new(),
+            algorithms: BTreeSet::from([HashAlgorithm::Sha256]),
+            parallel_blake3: true,
+            recent: Vec::new(),
+            expected: String::new(),
+            entries: Vec::new(),
+            jobs: Jobs::default(),
+            dialogs: FileDialogs::default(),
+            drop_notice: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            checksums: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            key_import: None,
+        }
+    }
+}
+
+/// How many entries [`HashCheckerApp::recent`] keeps.
+const MAX_RECENT: usize = 10;
+
+/// Shown wherever a non-cryptographic checksum appears.
+const NOT_CRYPTOGRAPHIC: &str = "Not cryptographic: this catches accidental corruption, but \
+     anyone can make a file with a matching checksum. Don't rely on it for security.";
+
+/// A file that has been queued for hashing, and how far along it is.
+struct Entry {
+    id: JobId,
+    name: String,
+    /// What was hashed, kept to hash it again with more algorithms.
+    source: JobSource,
+    state: EntryState,
+}
+
+impl Entry {
+    /// Where the file was read from, if it came from disk.
+    fn path(&self) -> Option<&Path> {
+        match &self.source {
+            JobSource::Path(path) => Some(path),
+            JobSource::Bytes { .. } => None,
+        }
+    }
+}
+
+enum EntryState {
+    Hashing(Option<Progress>),
+    Done(BTreeMap<HashAlgorithm, Digest>),
+    Failed(String),
+}
+
+/// A checksum file, such as `SHA256SUMS`, and who signed it.
+#[cfg(not(target_arch = "wasm32"))]
+struct SignedChecksums {
+    path: PathBuf,
+    file: ChecksumFile,
+    signer: Result<Signer, String>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SignedChecksums {
+    /// Reads the checksum file at `path` and checks its signature against the trusted keys.
+    fn open(path: PathBuf) -> Result<Self, String> {
+        let keys = TrustedKeys::default_dir()
+            .map_or_else(|| Ok(TrustedKeys::new()), |dir| TrustedKeys::load(&dir));
+        let (file, signer) = match keys {
+            Ok(keys) => ChecksumFile::read_signed(&path, None, None, &keys),
+            Err(err) => ChecksumFile::read(&path, None).map(|file| (file, Err(err))),
+        }
+        .map_err(|err| err.to_string())?;
+        Ok(Self {
+            path,
+            file,
+            signer: signer.map_err(|err| err.to_string()),
+        })
+    }
+
+    /// The entry for the file read from `path`, or called `name` if it didn't come from disk.
+    ///
+    /// A file is looked up by its path relative to the checksum file. Failing that, as when it
+    /// was downloaded somewhere else, it is looked up by file name, unless several of the files
+    /// listed have that name.
+    fn lookup(&self, path: Option<&Path>, name: &str) -> Lookup<'_> {
+        let dir = self.path.parent().unwrap_or(Path::new(""));
+        if let Some(relative) = path.and_then(|path| path.strip_prefix(dir).ok()) {
+            if let Some(entry) = self
+                .file
+                .entries
+                .iter()
+                .find(|entry| same_path(Path::new(&entry.file_name), relative))
+            {
+                return Lookup::Listed(entry);
+            }
+        }
+        let Some(name) = Path::new(name).file_name() else {
+            return Lookup::Unlisted;
+        };
+        let mut named = self
+            .file
+            .entries
+            .iter()
+            .filter(|entry| Path::new(&entry.file_name).file_name() == Some(name));
+        match named.next() {
+            None => Lookup::Unlisted,
+            Some(first) if named.any(|entry| entry.file_name != first.file_name) => {
+                Lookup::Ambiguous
+            }
+            Some(first) => Lookup::Listed(first),
+        }
+    }
+
+    fn signer_ui(&self, ui: &mut egui::Ui) {
+        match &self.signer {
+            Ok(signer) => {
+                let identity = if signer.comment.is_empty() {
+                    &signer.name
+                } else {
+                    &signer.comment
+                };
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(
+                        egui::RichText::new(format!("🔏 Signed by {identity}"))
+                            .color(egui::Color32::from_rgb(0, 170, 0)),
+                    )
+                    .on_hover_text(format!("{} key {:?}", signer.format, signer.name));
+                    ui.label(egui::RichText::new(&signer.fingerprint).monospace().weak());
+                });
+            }
+            Err(err) => {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    format!("⚠ Signature not verified: {err}"),
+                );
+            }
+        }
+    }
+}
+
+/// Where a hashed file appears in a [`SignedChecksums`].
+#[cfg(not(target_arch = "wasm32"))]
+enum Lookup<'a> {
+    Listed(&'a ChecksumEntry),
+    Unlisted,
+    /// The file's path isn't listed, and several files listed have its name.
+    Ambiguous,
+}
+
+/// Whether two relative paths name the same file, ignoring `.` components.
+#[cfg(not(target_arch = "wasm32"))]
+fn same_path(a: &Path, b: &Path) -> bool {
+    fn components(path: &Path) -> impl Iterator<Item = std::path::Component<'_>> {
+        path.components()
+            .filter(|component| *component != std::path::Component::CurDir)
+    }
+    components(a).eq(components(b))
+}
+
+/// The outcome of comparing an expected digest with the digests computed for a file.
+enum Verdict {
+    Match(HashAlgorithm),
+    Mismatch,
+    /// The expected digest isn't valid for any of the algorithms that were computed.
+    Incomparable,
+    /// The file isn't listed in the open checksum file.
+    #[cfg(not(target_arch = "wasm32"))]
+    Unlisted,
+    /// Only the file's name could be looked up, and several files listed have it.
+    #[cfg(not(target_arch = "wasm32"))]
+    Ambiguous,
+}
+
+impl Verdict {
+    fn new(expected: &str, hashes: &BTreeMap<HashAlgorithm, Digest>) -> Option<Self> {
+        if expected.trim().is_empty() {
+            return None;
+        }
+        let comparison = expected
+            .parse::<ExpectedDigest>()
+            .ok()
+            .and_then(|expected| expected.compare(hashes));
+        Some(match comparison {
+            Some(Comparison::Match(algorithm)) => Self::Match(algorithm),
+            Some(Comparison::Mismatch) => Self::Mismatch,
+            None => Self::Incomparable,
+        })
+    }
+
+    /// Compares the digests computed for a file with its entry in a checksum file.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn from_lookup(lookup: &Lookup<'_>, hashes: &BTreeMap<HashAlgorithm, Digest>) -> Self {
+        let entry = match lookup {
+            Lookup::Listed(entry) => entry,
+            Lookup::Unlisted => return Self::Unlisted,
+            Lookup::Ambiguous => return Self::Ambiguous,
+        };
+        let algorithm = entry.digest.algorithm();
+        match hashes.get(&algorithm) {
+            Some(digest) if *digest == entry.digest => Self::Match(algorithm),
+            Some(_) => Self::Mismatch,
+            None => Self::Incomparable,
         }
     }
+
+    fn ui(&self, ui: &mut egui::Ui) {
+        let (text, color) = match self {
+            Self::Match(algorithm) if !algorithm.is_cryptographic() => {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(
+                        egui::RichText::new(format!("✔ {algorithm} matches the expected checksum"))
+                            .color(egui::Color32::from_rgb(0, 170, 0))
+                            .strong(),
+                    );
+                    ui.label(
+                        egui::RichText::new("⚠ not cryptographic")
+                            .color(ui.visuals().warn_fg_color),
+                    )
+                    .on_hover_text(NOT_CRYPTOGRAPHIC);
+                });
+                return;
+            }
+            Self::Match(algorithm) => (
+                format!("✔ {algorithm} matches the expected hash"),
+                egui::Color32::from_rgb(0, 170, 0),
+            ),
+            Self::Mismatch => (
+                "✘ Does not match the expected hash".to_owned(),
+                ui.visuals().error_fg_color,
+            ),
+            Self::Incomparable => (
+                "The expected hash is not valid for any computed algorithm".to_owned(),
+                ui.visuals().warn_fg_color,
+            ),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Unlisted => (
+                "Not listed in the checksum file".to_owned(),
+                ui.visuals().warn_fg_color,
+            ),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Ambiguous => (
+                "Several files in the checksum file have this name; open the file from where the \
+                 checksum file lists it"
+                    .to_owned(),
+                ui.visuals().warn_fg_color,
+            ),
+        };
+        ui.label(egui::RichText::new(text).color(color).strong());
+    }
 }
 
-impl TemplateApp {
+impl HashCheckerApp {
     /// Called once before the first frame.
     #[must_use]
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
@@ -34,9 +317,509 @@ impl TemplateApp {
             Self::default()
         }
     }
+
+    /// The selected algorithms, plus every algorithm the expected digest could be from.
+    fn hash_algorithms(&self) -> Vec<HashAlgorithm> {
+        let mut algorithms = self.algorithms.clone();
+        if let Ok(expected) = self.expected.parse::<ExpectedDigest>() {
+            algorithms.extend(expected.algorithms());
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(Ok(checksums)) = &self.checksums {
+            algorithms.extend(
+                checksums
+                    .file
+                    .entries
+                    .iter()
+                    .map(|entry| entry.digest.algorithm()),
+            );
+        }
+        algorithms.into_iter().collect()
+    }
+
+    /// Queues `source` for hashing with [`Self::hash_algorithms`].
+    fn hash(&mut self, ctx: &egui::Context, source: JobSource) {
+        let name = source.name();
+        let algorithms = self.hash_algorithms();
+        self.drop_notice = None;
+        let id = self
+            .jobs
+            .spawn(ctx, source.clone(), algorithms, self.parallel_blake3);
+        self.entries.push(Entry {
+            id,
+            name,
+            source,
+            state: EntryState::Hashing(None),
+        });
+    }
+
+    /// Hashes finished entries again if the expected digest could be from an algorithm they
+    /// weren't hashed with, as when the published hash is pasted after the file was dropped.
+    fn rehash_for_expected(&mut self, ctx: &egui::Context) {
+        let Ok(expected) = self.expected.parse::<ExpectedDigest>() else {
+            return;
+        };
+        let candidates = expected.algorithms();
+        for entry in &mut self.entries {
+            let EntryState::Done(hashes) = &entry.state else {
+                continue;
+            };
+            if candidates
+                .iter()
+                .all(|algorithm| hashes.contains_key(algorithm))
+            {
+                continue;
+            }
+            let algorithms: BTreeSet<HashAlgorithm> =
+                hashes.keys().chain(&candidates).copied().collect();
+            entry.id = self.jobs.spawn(
+                ctx,
+                entry.source.clone(),
+                algorithms.into_iter().collect(),
+                self.parallel_blake3,
+            );
+            entry.state = EntryState::Hashing(None);
+        }
+    }
+
+    fn handle_job_events(&mut self) {
+        for event in self.jobs.poll() {
+            let Some(entry) = self.entries.iter_mut().find(|entry| entry.id == event.id()) else {
+                continue;
+            };
+            entry.state = match event {
+                JobEvent::Progress { progress, .. } => EntryState::Hashing(Some(progress)),
+                JobEvent::Finished {
+                    result: Ok(hashes), ..
+                } => EntryState::Done(hashes),
+                JobEvent::Finished {
+                    result: Err(err), ..
+                } => EntryState::Failed(err.to_string()),
+            };
+        }
+    }
+
+    /// Moves `path` to the top of the recently opened list.
+    fn remember(&mut self, path: PathBuf) {
+        self.recent.retain(|recent| *recent != path);
+        self.recent.insert(0, path);
+        self.recent.truncate(MAX_RECENT);
+    }
+
+    fn file_menu_ui(&mut self, ui: &mut egui::Ui) {
+        if ui.button("Open File(s)…").clicked() {
+            for path in self.dialogs.open_files(ui.ctx()) {
+                self.remember(path);
+            }
+        }
+
+        // NOTE: no folders, recent paths or File->Quit on web pages!
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if ui.button("Open Folder…").clicked() {
+                if let Some(folder) = self.dialogs.open_folder(ui.ctx()) {
+                    self.remember(folder);
+                }
+            }
+            ui.add_enabled_ui(!self.recent.is_empty(), |ui| {
+                ui.menu_button("Open Recent", |ui| {
+                    let mut opened = None;
+                    for path in &self.recent {
+                        if ui.button(path.display().to_string()).clicked() {
+                            opened = Some(path.clone());
+                        }
+                    }
+                    if let Some(path) = opened {
+                        self.dialogs.queue_path(ui.ctx(), path.clone());
+                        self.remember(path);
+                    }
+                });
+            });
+            ui.separator();
+            if ui.button("Quit").clicked() {
+                ui.ctx().send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+        }
+    }
+
+    /// Queues every file dropped onto the window this frame, and every file under any folders.
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped = ctx.input_mut(|i| std::mem::take(&mut i.raw.dropped_files));
+        if dropped.is_empty() {
+            return;
+        }
+        if self.hash_algorithms().is_empty() {
+            self.drop_notice = Some(
+                "Nothing was hashed: select an algorithm, then drop the files again".to_owned(),
+            );
+            return;
+        }
+        for file in dropped {
+            // Native backends give us a path, which may be a folder, and the web backend gives
+            // us the contents.
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(path) = file.path {
+                self.dialogs.queue_path(ctx, path);
+                continue;
+            }
+            let Some(data) = file.bytes else {
+                continue;
+            };
+            self.hash(
+                ctx,
+                JobSource::Bytes {
+                    name: file.name,
+                    data,
+                },
+            );
+        }
+    }
+
+    fn file_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("File:");
+            let hash_clicked = ui
+                .add_enabled(
+                    !self.hash_algorithms().is_empty(),
+                    egui::Button::new("Hash"),
+                )
+                .clicked();
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.path)
+                    .hint_text("Path to the file to check")
+                    .desired_width(f32::INFINITY),
+            );
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if (hash_clicked || submitted) && !self.path.trim().is_empty() {
+                self.hash(ui.ctx(), JobSource::Path(PathBuf::from(self.path.trim())));
+            }
+        });
+    }
+
+    fn algorithms_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Algorithms:");
+            for algorithm in HashAlgorithm::ALL {
+                if !algorithm.is_cryptographic() {
+                    continue;
+                }
+                let selected = self.algorithm_checkbox(ui, algorithm).1;
+                // There are no threads on the web to spread the work across.
+                if algorithm == HashAlgorithm::Blake3 && cfg!(not(target_arch = "wasm32")) {
+                    ui.add_enabled(
+                        selected,
+                        egui::Checkbox::new(&mut self.parallel_blake3, "multithreaded"),
+                    )
+                    .on_hover_text(format!(
+                        "Hash files of {} MiB or more on every core",
+                        crate::PARALLEL_BLAKE3_MIN_LEN / (1024 * 1024)
+                    ));
+                }
+            }
+        });
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Checksums (not cryptographic):")
+                .on_hover_text(NOT_CRYPTOGRAPHIC);
+            for algorithm in HashAlgorithm::ALL {
+                if !algorithm.is_cryptographic() {
+                    self.algorithm_checkbox(ui, algorithm)
+                        .0
+                        .on_hover_text(NOT_CRYPTOGRAPHIC);
+                }
+            }
+        });
+    }
+
+    /// A checkbox selecting `algorithm`, and whether it is now selected.
+    fn algorithm_checkbox(
+        &mut self,
+        ui: &mut egui::Ui,
+        algorithm: HashAlgorithm,
+    ) -> (egui::Response, bool) {
+        let mut selected = self.algorithms.contains(&algorithm);
+        let response = ui.checkbox(&mut selected, algorithm.name());
+        if response.changed() {
+            if selected {
+                self.algorithms.insert(algorithm);
+            } else {
+                self.algorithms.remove(&algorithm);
+            }
+        }
+        (response, selected)
+    }
+
+    fn expected_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Expected:");
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.expected)
+                    .hint_text("Paste the published hash to compare against")
+                    .font(egui::TextStyle::Monospace)
+                    .desired_width(f32::INFINITY),
+            );
+            if response.changed() {
+                self.rehash_for_expected(ui.ctx());
+            }
+        });
+        if self.expected.trim().is_empty() {
+            return;
+        }
+        match self.expected.parse::<ExpectedDigest>() {
+            Ok(expected) => {
+                let names: Vec<&str> = expected
+                    .algorithms()
+                    .into_iter()
+                    .map(HashAlgorithm::name)
+                    .collect();
+                ui.weak(format!("Looks like {}", names.join(" or ")));
+            }
+            Err(err) => {
+                ui.colored_label(ui.visuals().warn_fg_color, err.to_string());
+            }
+        }
+    }
+
+    /// Lets the user open a checksum file, signed or not, to check files against, and trust the
+    /// keys it is signed with.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn checksum_file_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Checksum file:");
+            if ui
+                .button("Open…")
+                .on_hover_text("Check files against a checksum file such as SHA256SUMS")
+                .clicked()
+            {
+                if let Some(path) = crate::file_dialog::pick_file("Open Checksum File") {
+                    self.checksums = Some(SignedChecksums::open(path));
+                }
+            }
+            if ui
+                .button("Import Key…")
+                .on_hover_text("Trust a minisign, signify, SSH or OpenPGP public key")
+                .clicked()
+            {
+                self.import_key();
+            }
+            if let Some(checksums) = &self.checksums {
+                if let Ok(checksums) = checksums {
+                    ui.label(checksums.path.display().to_string());
+                }
+                if ui.button("Close").clicked() {
+                    self.checksums = None;
+                }
+            }
+        });
+        match &self.checksums {
+            Some(Ok(checksums)) => checksums.signer_ui(ui),
+            Some(Err(err)) => {
+                ui.colored_label(ui.visuals().error_fg_color, err);
+            }
+            None => {}
+        }
+        match &self.key_import {
+            Some(Ok(message)) => {
+                ui.weak(message);
+            }
+            Some(Err(err)) => {
+                ui.colored_label(ui.visuals().error_fg_color, err);
+            }
+            None => {}
+        }
+    }
+
+    /// Copies a public key the user picks into the trusted key store.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn import_key(&mut self) {
+        let Some(path) = crate::file_dialog::pick_file("Import Public Key") else {
+            return;
+        };
+        let name = crate::signature::key_name(&path);
+        self.key_import = Some(match TrustedKeys::default_dir() {
+            Some(dir) => TrustedKeys::install(&dir, &name, &path)
+                .map(|_| format!("Trusted {} as {name:?}", path.display()))
+                .map_err(|err| err.to_string()),
+            None => Err("There is nowhere to keep trusted keys on this system".to_owned()),
+        });
+        // The open checksum file may be signed with the new key.
+        if let Some(Ok(checksums)) = &self.checksums {
+            self.checksums = Some(SignedChecksums::open(checksums.path.clone()));
+        }
+    }
+
+    /// A manifest of every finished file's `algorithm` digest, with paths relative to the
+    /// deepest folder containing all of them.
+    fn manifest(&self, algorithm: HashAlgorithm) -> Manifest {
+        let finished: Vec<(&Path, &Digest)> = self
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                let EntryState::Done(hashes) = &entry.state else {
+                    return None;
+                };
+                let path = entry.path().unwrap_or(Path::new(&entry.name));
+                Some((path, hashes.get(&algorithm)?))
+            })
+            .collect();
+        let base_dir = common_parent(finished.iter().map(|(path, _)| *path));
+        let mut manifest = Manifest::new();
+        for (path, digest) in finished {
+            manifest.insert(&base_dir, path, digest.clone());
+        }
+        manifest
+    }
+
+    fn export_menu_ui(&self, ui: &mut egui::Ui) {
+        let algorithms: BTreeSet<HashAlgorithm> = self
+            .entries
+            .iter()
+            .filter_map(|entry| match &entry.state {
+                EntryState::Done(hashes) => Some(hashes.keys().copied()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        for algorithm in algorithms {
+            ui.menu_button(algorithm.name(), |ui| {
+                for format in ManifestFormat::ALL {
+                    if !format.supports(algorithm) {
+                        continue;
+                    }
+                    if ui.button(format.name()).clicked() {
+                        let contents = self.manifest(algorithm).render(format);
+                        crate::file_dialog::save_file(
+                            ui.ctx(),
+                            &format.file_name(algorithm),
+                            &contents,
+                        );
+                    }
+                }
+            });
+        }
+    }
+
+    fn entries_ui(&self, ui: &mut egui::Ui) {
+        let mut cancel = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for entry in &self.entries {
+                ui.group(|ui| {
+                    ui.set_width(ui.available_width());
+                    ui.label(egui::RichText::new(&entry.name).strong());
+                    match &entry.state {
+                        EntryState::Hashing(progress) => {
+                            ui.horizontal(|ui| {
+                                if ui.button("Cancel").clicked() {
+                                    cancel = Some(entry.id);
+                                }
+                                progress_ui(ui, progress.as_ref());
+                            });
+                        }
+                        EntryState::Done(hashes) => {
+                            digests_ui(ui, entry.id, hashes);
+                            if let Some(verdict) = Verdict::new(&self.expected, hashes) {
+                                verdict.ui(ui);
+                            } else {
+                                #[cfg(not(target_arch = "wasm32"))]
+                                if let Some(Ok(checksums)) = &self.checksums {
+                                    let lookup = checksums.lookup(entry.path(), &entry.name);
+                                    Verdict::from_lookup(&lookup, hashes).ui(ui);
+                                    checksums.signer_ui(ui);
+                                }
+                            }
+                        }
+                        EntryState::Failed(err) => {
+                            ui.colored_label(ui.visuals().error_fg_color, err);
+                        }
+                    }
+                });
+            }
+        });
+        if let Some(id) = cancel {
+            self.jobs.cancel(id);
+        }
+    }
 }
 
-impl eframe::App for TemplateApp {
+fn progress_ui(ui: &mut egui::Ui, progress: Option<&Progress>) {
+    let Some(progress) = progress else {
+        ui.add(egui::ProgressBar::new(0.0).animate(true));
+        return;
+    };
+    let text = format!(
+        "{} at {}/s",
+        format_bytes(progress.bytes_processed as f64),
+        format_bytes(progress.throughput())
+    );
+    ui.add(
+        egui::ProgressBar::new(progress.fraction().unwrap_or(0.0))
+            .text(text)
+            .animate(true),
+    );
+}
+
+fn digests_ui(ui: &mut egui::Ui, id: JobId, hashes: &BTreeMap<HashAlgorithm, Digest>) {
+    egui::Grid::new(id)
+        .num_columns(3)
+        .striped(true)
+        .show(ui, |ui| {
+            for (algorithm, digest) in hashes {
+                if algorithm.is_cryptographic() {
+                    ui.label(algorithm.name());
+                } else {
+                    ui.label(format!("{} ⚠", algorithm.name()))
+                        .on_hover_text(NOT_CRYPTOGRAPHIC);
+                }
+                ui.add(
+                    egui::Label::new(egui::RichText::new(digest.to_string()).monospace()).wrap(),
+                );
+                if ui.small_button("📋").on_hover_text("Copy").clicked() {
+                    ui.ctx().copy_text(digest.to_string());
+                }
+                ui.end_row();
+            }
+        });
+    if let Some(integrity) = Integrity::from_digests(hashes.values()) {
+        let integrity = integrity.to_string();
+        if ui
+            .button("📋 Copy SRI")
+            .on_hover_text(format!("Copy for an HTML integrity attribute: {integrity}"))
+            .clicked()
+        {
+            ui.ctx().copy_text(integrity);
+        }
+    }
+}
+
+/// The deepest folder containing every one of `paths`.
+fn common_parent<'a>(paths: impl IntoIterator<Item = &'a Path>) -> PathBuf {
+    let mut common: Option<PathBuf> = None;
+    for path in paths {
+        let parent = path.parent().unwrap_or(Path::new(""));
+        common = Some(match common {
+            None => parent.to_owned(),
+            Some(common) => common
+                .components()
+                .zip(parent.components())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect(),
+        });
+    }
+    common.unwrap_or_default()
+}
+
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+impl eframe::App for HashCheckerApp {
     /// Called by the framework to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         eframe::set_value(storage, eframe::APP_KEY, self);
@@ -44,55 +827,101 @@ impl eframe::App for TemplateApp {
 
     /// Called each time the UI needs repainting, which may be many times per second.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Put your widgets into a `SidePanel`, `TopBottomPanel`, `CentralPanel`, `Window` or `Area`.
-        // For inspiration and more examples, go to https://emilk.github.io/egui
+        self.handle_job_events();
+        self.handle_dropped_files(ctx);
+        for source in self.dialogs.poll() {
+            self.hash(ctx, source);
+        }
 
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             // The top panel is often a good place for a menu bar:
 
             egui::MenuBar::new().ui(ui, |ui| {
-                // NOTE: no File->Quit on web pages!
-                let is_web = cfg!(target_arch = "wasm32");
-                if !is_web {
-                    ui.menu_button("File", |ui| {
-                        if ui.button("Quit").clicked() {
-                            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-                        }
-                    });
-                    ui.add_space(16.0);
-                }
+                ui.menu_button("File", |ui| self.file_menu_ui(ui));
+                ui.add_space(16.0);
 
                 egui::widgets::global_theme_preference_buttons(ui);
             });
         });
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            // The central panel the region left after adding TopPanel's and SidePanel's
-            ui.heading("eframe template");
-
+        egui::TopBottomPanel::bottom("bottom_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
-                ui.label("Write something: ");
-                ui.text_edit_singleline(&mut self.label);
+                ui.add(egui::github_link_file!(
+                    "https://github.com/william-o-s/hash-checker/blob/main/",
+                    "Source code."
+                ));
+                ui.separator();
+                powered_by_egui_and_eframe(ui);
+                egui::warn_if_debug_build(ui);
             });
+        });
 
-            ui.add(egui::Slider::new(&mut self.value, 0.0..=10.0).text("value"));
-            if ui.button("Increment").clicked() {
-                self.value += 1.0;
-            }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Hash Checker");
 
-            ui.separator();
+            self.file_ui(ui);
+            self.algorithms_ui(ui);
+            self.expected_ui(ui);
+            #[cfg(not(target_arch = "wasm32"))]
+            self.checksum_file_ui(ui);
 
-            ui.add(egui::github_link_file!(
-                "https://github.com/emilk/eframe_template/blob/main/",
-                "Source code."
-            ));
+            ui.separator();
 
-            ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
-                powered_by_egui_and_eframe(ui);
-                egui::warn_if_debug_build(ui);
+            ui.horizontal(|ui| {
+                ui.heading("Results");
+                if ui
+                    .add_enabled(!self.entries.is_empty(), egui::Button::new("Clear"))
+                    .clicked()
+                {
+                    self.jobs.cancel_all();
+                    self.entries.clear();
+                    self.drop_notice = None;
+                }
+                let any_done = self
+                    .entries
+                    .iter()
+                    .any(|entry| matches!(entry.state, EntryState::Done(_)));
+                ui.add_enabled_ui(any_done, |ui| {
+                    ui.menu_button("Export checksums", |ui| self.export_menu_ui(ui))
+                        .response
+                        .on_hover_text("Save the results as a checksum file such as SHA256SUMS");
+                });
             });
+            if let Some(notice) = &self.drop_notice {
+                ui.colored_label(ui.visuals().warn_fg_color, notice);
+            }
+            self.entries_ui(ui);
         });
+
+        drop_zone_ui(ctx);
+    }
+}
+
+/// Dims the window and explains what will happen while files are dragged over it.
+fn drop_zone_ui(ctx: &egui::Context) {
+    let hovered = ctx.input(|i| i.raw.hovered_files.len());
+    if hovered == 0 {
+        return;
     }
+    let text = if hovered == 1 {
+        "Drop to hash this file".to_owned()
+    } else {
+        format!("Drop to hash these {hovered} files")
+    };
+
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Foreground,
+        egui::Id::new("drop_zone"),
+    ));
+    let screen_rect = ctx.screen_rect();
+    painter.rect_filled(screen_rect, 0.0, egui::Color32::from_black_alpha(192));
+    painter.text(
+        screen_rect.center(),
+        egui::Align2::CENTER_CENTER,
+        text,
+        egui::TextStyle::Heading.resolve(&ctx.style()),
+        egui::Color32::WHITE,
+    );
 }
 
 fn powered_by_egui_and_eframe(ui: &mut egui::Ui) {
@@ -108,3 +937,82 @@ fn powered_by_egui_and_eframe(ui: &mut egui::Ui) {
         ui.label(".");
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use web_time::Instant;
+
+    fn wait_for_jobs(app: &mut HashCheckerApp) {
+        let start = Instant::now();
+        while app.jobs.is_busy() {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "job did not finish"
+            );
+            app.handle_job_events();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        app.handle_job_events();
+    }
+
+    #[test]
+    fn test_expected_pasted_after_hashing() {
+        let ctx = egui::Context::default();
+        let mut app = HashCheckerApp::default();
+        app.hash(&ctx, JobSource::Path(PathBuf::from("examples/valid.txt")));
+        wait_for_jobs(&mut app);
+
+        // Only SHA-256 was selected, so the file is hashed again to compare an MD5 digest.
+        app.expected = "b2cfa4183267af678ea06c7407d4d6d8".to_owned();
+        app.rehash_for_expected(&ctx);
+        wait_for_jobs(&mut app);
+        let EntryState::Done(hashes) = &app.entries[0].state else {
+            panic!("Expected valid.txt to hash successfully.");
+        };
+        assert!(hashes.contains_key(&HashAlgorithm::Sha256));
+        assert!(matches!(
+            Verdict::new(&app.expected, hashes),
+            Some(Verdict::Match(HashAlgorithm::Md5))
+        ));
+
+        // Nothing is hashed again once every candidate has been computed.
+        let id = app.entries[0].id;
+        app.rehash_for_expected(&ctx);
+        assert_eq!(app.entries[0].id, id);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_checksum_lookup() {
+        let a = "a".repeat(64);
+        let b = "b".repeat(64);
+        let checksums = SignedChecksums {
+            path: PathBuf::from("/downloads/SHA256SUMS"),
+            file: ChecksumFile::parse(
+                &format!("{a}  a/setup.exe\n{b}  ./b/setup.exe\n{a}  notes.txt\n"),
+                None,
+            ),
+            signer: Err("unsigned".to_owned()),
+        };
+        let listed =
+            |path: Option<&str>, name: &str| match checksums.lookup(path.map(Path::new), name) {
+                Lookup::Listed(entry) => Some(entry.file_name.clone()),
+                Lookup::Unlisted => None,
+                Lookup::Ambiguous => Some("ambiguous".to_owned()),
+            };
+
+        let setup = "/downloads/b/setup.exe";
+        assert_eq!(listed(Some(setup), setup).as_deref(), Some("./b/setup.exe"));
+        let setup = "/elsewhere/setup.exe";
+        assert_eq!(listed(Some(setup), setup).as_deref(), Some("ambiguous"));
+        assert_eq!(listed(None, "setup.exe").as_deref(), Some("ambiguous"));
+
+        // A unique name is found wherever the file is.
+        let notes = "/elsewhere/notes.txt";
+        assert_eq!(listed(Some(notes), notes).as_deref(), Some("notes.txt"));
+        assert_eq!(listed(None, "notes.txt").as_deref(), Some("notes.txt"));
+        assert_eq!(listed(None, "other.txt"), None);
+    }
+}
@@ -0,0 +1,467 @@
+//! Hashing many files at once on a bounded pool of worker threads
+
+use crate::{CancellationToken, Digest, HashAlgorithm, HashError, HashOptions, ReadStrategy};
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex, mpsc};
+
+/// How many files a batch hashes at once, and how each is read.
+///
+/// Each worker reads its file through a fixed-size buffer and at most a couple of finished
+/// results per worker wait to be collected, so memory use is bounded by the worker count rather
+/// than by the number or size of the files. This holds for [`Batch::ordered`] too, as workers
+/// then don't start a file more than that far past the oldest one still being hashed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BatchOptions {
+    workers: NonZeroUsize,
+    read_strategy: ReadStrategy,
+    parallel_blake3: Option<u64>,
+}
+
+impl Default for BatchOptions {
+    /// One worker per available CPU core.
+    fn default() -> Self {
+        Self {
+            workers: std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+            read_strategy: ReadStrategy::default(),
+            parallel_blake3: None,
+        }
+    }
+}
+
+impl BatchOptions {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hashes up to `workers` files at once. Zero is treated as one.
+    #[must_use]
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = NonZeroUsize::new(workers).unwrap_or(NonZeroUsize::MIN);
+        self
+    }
+
+    /// The number of files hashed at once.
+    #[must_use]
+    pub fn worker_count(&self) -> usize {
+        self.workers.get()
+    }
+
+    /// Reads every file with `read_strategy`.
+    #[must_use]
+    pub fn read_strategy(mut self, read_strategy: ReadStrategy) -> Self {
+        self.read_strategy = read_strategy;
+        self
+    }
+
+    /// Hashes files of at least `min_len` bytes with BLAKE3 on every core.
+    /// See [`HashOptions::parallel_blake3`].
+    ///
+    /// BLAKE3 brings its own pool of one thread per core, so this pays off most when a few
+    /// large files would otherwise leave cores idle.
+    #[must_use]
+    pub fn parallel_blake3(mut self, min_len: u64) -> Self {
+        self.parallel_blake3 = Some(min_len);
+        self
+    }
+
+    /// The options each file in the batch is hashed with.
+    pub(crate) fn hash_options(&self, token: &CancellationToken) -> HashOptions<'static> {
+        let options = HashOptions::new()
+            .cancellation(token.clone())
+            .read_strategy(self.read_strategy);
+        match self.parallel_blake3 {
+            Some(min_len) => options.parallel_blake3(min_len),
+            None => options,
+        }
+    }
+}
+
+/// The outcome of hashing one file in a [`Batch`].
+#[derive(Debug)]
+pub struct HashedPath {
+    pub path: PathBuf,
+    pub result: Result<BTreeMap<HashAlgorithm, Digest>, HashError>,
+}
+
+/// The results of a batch of work, yielded as each item finishes together with its position
+/// in the input.
+///
+/// Use [`Batch::ordered`] to get the results back in input order instead.
+/// Dropping the batch cancels any hashing still in progress.
+pub struct Batch<T> {
+    inner: Inner<T>,
+    cancellation: CancellationToken,
+}
+
+/// The items workers may start, shared between a threaded [`Batch`] and its workers.
+///
+/// Every item may start until the batch is [ordered](Batch::ordered). From then on workers
+/// stay within `lookahead` items of the oldest result still awaited, so results held back for
+/// reordering can't pile up behind a slow item.
+struct Window {
+    /// Items with an index below this may start.
+    end: Mutex<usize>,
+    advanced: Condvar,
+    lookahead: usize,
+}
+
+impl Window {
+    fn new(lookahead: usize) -> Self {
+        Self {
+            end: Mutex::new(usize::MAX),
+            advanced: Condvar::new(),
+            lookahead,
+        }
+    }
+
+    /// Lets workers start items up to `lookahead` past `next`, the oldest result awaited.
+    fn advance(&self, next: usize) {
+        self.set_end(next.saturating_add(self.lookahead));
+    }
+
+    fn set_end(&self, end: usize) {
+        if let Ok(mut current) = self.end.lock() {
+            *current = end;
+        }
+        self.advanced.notify_all();
+    }
+
+    /// Blocks until the item at `index` may start.
+    fn wait_for(&self, index: usize) {
+        if let Ok(end) = self.end.lock() {
+            drop(self.advanced.wait_while(end, |end| index >= *end));
+        }
+    }
+}
+
+enum Inner<T> {
+    /// Items are processed on worker threads and sent back over a bounded channel.
+    Threads(mpsc::Receiver<(usize, T)>, Arc<Window>),
+
+    /// Items are processed one at a time as the batch is iterated, where threads are
+    /// unavailable or pointless.
+    Inline(Box<dyn Iterator<Item = (usize, T)> + Send>),
+}
+
+impl<T> Iterator for Batch<T> {
+    type Item = (usize, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            Inner::Threads(receiver, _) => receiver.recv().ok(),
+            Inner::Inline(items) => items.next(),
+        }
+    }
+}
+
+impl<T> Drop for Batch<T> {
+    fn drop(&mut self) {
+        self.cancellation.cancel();
+        // Wake workers waiting for the window to advance, so they see the cancellation.
+        if let Inner::Threads(_, window) = &self.inner {
+            window.set_end(usize::MAX);
+        }
+    }
+}
+
+impl<T> Batch<T> {
+    /// Yields results in the order their inputs were given.
+    ///
+    /// Results that finish early are held back until everything before them is done, so a slow
+    /// item near the start of the input delays everything after it. Only a couple of items per
+    /// worker are started past the slow one, after which workers wait for it.
+    #[must_use]
+    pub fn ordered(self) -> Ordered<T> {
+        if let Inner::Threads(_, window) = &self.inner {
+            window.advance(0);
+        }
+        Ordered {
+            batch: self,
+            next: 0,
+            pending: BTreeMap::new(),
+        }
+    }
+}
+
+/// The results of a [`Batch`] in input order. See [`Batch::ordered`].
+pub struct Ordered<T> {
+    batch: Batch<T>,
+    next: usize,
+    pending: BTreeMap<usize, T>,
+}
+
+impl<T> Iterator for Ordered<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.pending.remove(&self.next) {
+                self.next += 1;
+                if let Inner::Threads(_, window) = &self.batch.inner {
+                    window.advance(self.next);
+                }
+                return Some(item);
+            }
+            let (index, item) = self.batch.next()?;
+            self.pending.insert(index, item);
+        }
+    }
+}
+
+/// Applies `f` to every item on `options`' worker threads, pulling items from `items` only as
+/// workers become free.
+///
+/// `f` is given a token that is cancelled once the returned batch is dropped.
+pub(crate) fn parallel_map<I, R, F>(items: I, options: &BatchOptions, f: F) -> Batch<R>
+where
+    I: Iterator + Send + 'static,
+    R: Send + 'static,
+    F: Fn(I::Item, &CancellationToken) -> R + Send + Sync + 'static,
+{
+    let cancellation = CancellationToken::new();
+
+    // There are no threads on the web.
+    if cfg!(target_arch = "wasm32") || options.worker_count() == 1 {
+        let token = cancellation.clone();
+        let items = items
+            .enumerate()
+            .map(move |(index, item)| (index, f(item, &token)));
+        return Batch {
+            inner: Inner::Inline(Box::new(items)),
+            cancellation,
+        };
+    }
+
+    let workers = options.worker_count();
+    let (sender, receiver) = mpsc::sync_channel(workers * 2);
+    let window = Arc::new(Window::new(workers * 2));
+    let items = Arc::new(Mutex::new(items.enumerate()));
+    let f = Arc::new(f);
+    for worker in 0..workers {
+        let sender = sender.clone();
+        let items = Arc::clone(&items);
+        let f = Arc::clone(&f);
+        let window = Arc::clone(&window);
+        let token = cancellation.clone();
+        std::thread::Builder::new()
+            .name(format!("hash-batch-{worker}"))
+            .spawn(move || {
+                loop {
+                    let next = items.lock().map_or(None, |mut items| items.next());
+                    let Some((index, item)) = next else {
+                        break;
+                    };
+                    window.wait_for(index);
+                    if token.is_cancelled() || sender.send((index, f(item, &token))).is_err() {
+                        break;
+                    }
+                }
+            })
+            .expect("Failed to spawn hashing thread");
+    }
+    Batch {
+        inner: Inner::Threads(receiver, window),
+        cancellation,
+    }
+}
+
+/// A task run by a [`WorkerPool`].
+#[cfg(not(target_arch = "wasm32"))]
+type Task = Box<dyn FnOnce() + Send>;
+
+/// A fixed number of threads that run tasks in the order they were queued.
+///
+/// Threads are started as tasks arrive, up to the limit, and exit once the pool is dropped and
+/// the queue has drained.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) struct WorkerPool {
+    sender: mpsc::Sender<Task>,
+    receiver: Arc<Mutex<mpsc::Receiver<Task>>>,
+    options: BatchOptions,
+    spawned: usize,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl WorkerPool {
+    pub(crate) fn new(options: BatchOptions) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            sender,
+            receiver: Arc::new(Mutex::new(receiver)),
+            options,
+            spawned: 0,
+        }
+    }
+
+    /// Queues `task` to run on the next free thread.
+    pub(crate) fn execute(&mut self, task: impl FnOnce() + Send + 'static) {
+        self.sender.send(Box::new(task)).ok();
+        if self.spawned < self.options.worker_count() {
+            let receiver = Arc::clone(&self.receiver);
+            std::thread::Builder::new()
+                .name(format!("hash-worker-{}", self.spawned))
+                .spawn(move || {
+                    loop {
+                        // Holding the lock while waiting is fine: whoever gets it next runs the
+                        // next task, which is all the other threads are waiting for anyway.
+                        let task = receiver
+                            .lock()
+                            .map_or(None, |receiver| receiver.recv().ok());
+                        let Some(task) = task else {
+                            break;
+                        };
+                        task();
+                    }
+                })
+                .expect("Failed to spawn hashing thread");
+            self.spawned += 1;
+        }
+    }
+}
+
+/// Hashes every one of `paths` with every one of `algorithms`, several files at a time.
+///
+/// # Arguments
+///
+/// * `paths` - The files to hash. They are only pulled from the iterator as workers become free,
+///   so it may lazily produce a very long list.
+/// * `algorithms` - The algorithms to hash every file with.
+/// * `options` - How many files to hash at once.
+///
+/// # Returns
+///
+/// An iterator over each file's result and its position in `paths`, in the order hashing
+/// finished. Call [`Batch::ordered`] to restore the input order.
+///
+/// # Examples
+///
+/// ```rust
+/// use hash_checker::{BatchOptions, HashAlgorithm, hash_batch};
+/// let paths = vec!["examples/valid.txt".into(), "examples/SHA256SUMS".into()];
+/// let batch = hash_batch(paths, &[HashAlgorithm::Sha256], &BatchOptions::new().workers(2));
+/// for hashed in batch.ordered() {
+///     println!("{}: {:?}", hashed.path.display(), hashed.result);
+/// }
+/// ```
+pub fn hash_batch<I>(
+    paths: I,
+    algorithms: &[HashAlgorithm],
+    options: &BatchOptions,
+) -> Batch<HashedPath>
+where
+    I: IntoIterator<Item = PathBuf>,
+    I::IntoIter: Send + 'static,
+{
+    let algorithms = algorithms.to_vec();
+    let batch = *options;
+    parallel_map(paths.into_iter(), options, move |path, token| {
+        let result = crate::hash_file_with(&path, &algorithms, batch.hash_options(token));
+        HashedPath { path, result }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parallel_map_restores_order() {
+        for workers in [1, 4] {
+            let options = BatchOptions::new().workers(workers);
+            let batch = parallel_map(0..100_u64, &options, |n, _| {
+                // Make later items finish first.
+                std::thread::sleep(std::time::Duration::from_micros(100 - n));
+                n * 2
+            });
+            let doubled: Vec<u64> = batch.ordered().collect();
+            assert_eq!(doubled, (0..100).map(|n| n * 2).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_ordered_stays_near_slow_items() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let started = Arc::new(AtomicUsize::new(0));
+        let options = BatchOptions::new().workers(4);
+        let batch = parallel_map(0..1000_usize, &options, {
+            let started = Arc::clone(&started);
+            move |n, _| {
+                started.fetch_max(n, Ordering::SeqCst);
+                if n == 0 {
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                }
+                n
+            }
+        });
+        let mut ordered = batch.ordered();
+        assert_eq!(ordered.next(), Some(0));
+        // A few items may start before the batch is ordered, but not the whole input.
+        let furthest = started.load(Ordering::SeqCst);
+        assert!(
+            furthest < 32,
+            "started item {furthest} while item 0 was hashing"
+        );
+        assert!(ordered.eq(1..1000));
+    }
+
+    #[test]
+    fn test_hash_batch() {
+        let paths = vec![
+            PathBuf::from("examples/valid.txt"),
+            PathBuf::from("examples/missing.txt"),
+            PathBuf::from("examples/valid.txt"),
+        ];
+        let results: Vec<HashedPath> = hash_batch(
+            paths,
+            &[HashAlgorithm::Sha256],
+            &BatchOptions::new().workers(3),
+        )
+        .ordered()
+        .collect();
+        assert_eq!(results.len(), 3);
+        assert!(matches!(results[1].result, Err(HashError::NotFound { .. })));
+        for hashed in [&results[0], &results[2]] {
+            let hashes = hashed.result.as_ref().expect("Expected valid.txt to hash.");
+            assert_eq!(
+                hashes[&HashAlgorithm::Sha256].to_string(),
+                "6d78392a5886177fe5b86e585a0b695a2bcd01a05504b3c4e38bc8eeb21e8326"
+            );
+        }
+    }
+
+    #[test]
+    fn test_dropping_stops_workers() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let workers = 2;
+        let calls = Arc::new(AtomicUsize::new(0));
+        let options = BatchOptions::new().workers(workers);
+        let mut batch = parallel_map(0.., &options, {
+            let calls = Arc::clone(&calls);
+            move |n: u64, _| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                n
+            }
+        });
+        assert!(batch.next().is_some());
+        drop(batch);
+
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        let after_drop = calls.load(Ordering::SeqCst);
+        // The one result taken, a full channel, and one more result per worker waiting to be sent.
+        assert!(
+            after_drop <= 1 + workers * 2 + workers,
+            "{after_drop} items were processed"
+        );
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert_eq!(
+            calls.load(Ordering::SeqCst),
+            after_drop,
+            "workers kept going"
+        );
+    }
+}
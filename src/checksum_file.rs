@@ -0,0 +1,721 @@
+//! Checksum files in the format of GNU coreutils' `sha256sum` and friends, and SFV files
+
+use crate::digest::default_algorithm;
+use crate::{
+    BatchOptions, Digest, HashAlgorithm, HashError, Signature, SignatureError, Signer, TrustedKeys,
+};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// One line of a checksum file: the expected digest of a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChecksumEntry {
+    /// The 1-based line number the entry was read from.
+    pub line: usize,
+
+    /// The file name, relative to the directory containing the checksum file.
+    pub file_name: String,
+
+    /// The expected digest of the file.
+    pub digest: Digest,
+
+    /// Whether the entry was marked as binary with `*` rather than as text with a space.
+    pub binary: bool,
+}
+
+/// The parsed contents of a checksum file such as `SHA256SUMS` or `release.sfv`.
+///
+/// Both the default `sha256sum` format (`<hex>  <file>` or `<hex> *<file>`) and the BSD
+/// tagged format (`SHA256 (<file>) = <hex>`) are understood, as is the leading backslash GNU
+/// tools use to mark file names containing escaped backslashes or newlines.
+/// SFV files are read with [`ChecksumFile::parse_sfv`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChecksumFile {
+    /// The well-formed entries, in file order.
+    pub entries: Vec<ChecksumEntry>,
+
+    /// The 1-based line numbers of lines that could not be parsed.
+    pub malformed_lines: Vec<usize>,
+}
+
+impl ChecksumFile {
+    /// Parses the text of a checksum file.
+    ///
+    /// Untagged lines are assumed to use `algorithm`. If it is `None`, the algorithm of each
+    /// untagged line is guessed from its length, as `cksum -c` does.
+    ///
+    /// Of a clearsigned OpenPGP message, such as Fedora's `CHECKSUM` files, only the signed text
+    /// is read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use hash_checker::{ChecksumFile, HashAlgorithm};
+    /// let file = ChecksumFile::parse(
+    ///     "6d78392a5886177fe5b86e585a0b695a2bcd01a05504b3c4e38bc8eeb21e8326 *valid.txt\n",
+    ///     Some(HashAlgorithm::Sha256),
+    /// );
+    /// assert_eq!(file.entries[0].file_name, "valid.txt");
+    /// assert!(file.entries[0].binary);
+    /// ```
+    #[must_use]
+    pub fn parse(text: &str, algorithm: Option<HashAlgorithm>) -> Self {
+        if let Some((armor_lines, signed_text)) = crate::openpgp::signed_text(text) {
+            let mut file = Self::parse_lines(&signed_text, algorithm);
+            // Number lines as they appear in the message, not in the signed text.
+            for entry in &mut file.entries {
+                entry.line += armor_lines;
+            }
+            for line in &mut file.malformed_lines {
+                *line += armor_lines;
+            }
+            return file;
+        }
+        Self::parse_lines(text, algorithm)
+    }
+
+    fn parse_lines(text: &str, algorithm: Option<HashAlgorithm>) -> Self {
+        let mut file = Self::default();
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match parse_line(line, algorithm) {
+                Some((file_name, digest, binary)) => file.entries.push(ChecksumEntry {
+                    line: line_number,
+                    file_name,
+                    digest,
+                    binary,
+                }),
+                None => file.malformed_lines.push(line_number),
+            }
+        }
+        file
+    }
+
+    /// Parses the text of an SFV (Simple File Verification) file: a `<file> <CRC-32>` line for
+    /// each file, with comment lines starting with `;`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use hash_checker::{ChecksumFile, HashAlgorithm};
+    /// let file = ChecksumFile::parse_sfv("; Generated by hand\nmy file.txt E0117757\n");
+    /// assert_eq!(file.entries[0].file_name, "my file.txt");
+    /// assert_eq!(file.entries[0].digest.algorithm(), HashAlgorithm::Crc32);
+    /// ```
+    #[must_use]
+    pub fn parse_sfv(text: &str) -> Self {
+        let mut file = Self::default();
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim_end();
+            if line.trim_start().is_empty() || line.starts_with(';') {
+                continue;
+            }
+            match parse_sfv_line(line) {
+                Some((file_name, digest)) => file.entries.push(ChecksumEntry {
+                    line: line_number,
+                    file_name: file_name.to_owned(),
+                    digest,
+                    binary: false,
+                }),
+                None => file.malformed_lines.push(line_number),
+            }
+        }
+        file
+    }
+
+    /// Reads and parses the checksum file at `path`.
+    ///
+    /// Files ending in `.sfv` are read as SFV, which always uses CRC-32. Otherwise, if
+    /// `algorithm` is `None`, it is guessed from the file name (e.g. `SHA256SUMS` or
+    /// `release.sha512`) and failing that from the length of each line's digest.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the checksum file cannot be read.
+    pub fn read(
+        path: impl AsRef<Path>,
+        algorithm: Option<HashAlgorithm>,
+    ) -> Result<Self, HashError> {
+        let path = path.as_ref();
+        let contents = crate::hashing::read_file(path)?;
+        Ok(Self::from_contents(path, &contents, algorithm))
+    }
+
+    /// Reads and parses the checksum file at `path`, like [`ChecksumFile::read`], and checks
+    /// that it was signed by one of `keys`.
+    ///
+    /// The signature is read from `signature`, or if that is `None` from the file itself if it
+    /// is a clearsigned OpenPGP message, or else from a file next to it as found by
+    /// [`crate::find_signature`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the checksum file cannot be read. Problems with the signature are
+    /// returned alongside the parsed file.
+    pub fn read_signed(
+        path: impl AsRef<Path>,
+        signature: Option<&Path>,
+        algorithm: Option<HashAlgorithm>,
+        keys: &TrustedKeys,
+    ) -> Result<(Self, Result<Signer, SignatureError>), HashError> {
+        let path = path.as_ref();
+        let contents = crate::hashing::read_file(path)?;
+        // Parse exactly the bytes whose signature is checked, so the file can't change in between.
+        let file = Self::from_contents(path, &contents, algorithm);
+        let signature = match signature {
+            Some(signature) => Signature::read(signature),
+            None if crate::openpgp::is_clearsigned(&contents) => Signature::parse_bytes(&contents),
+            None => crate::find_signature(path)
+                .ok_or_else(|| SignatureError::NotFound {
+                    path: path.to_owned(),
+                })
+                .and_then(Signature::read),
+        };
+        let signer = signature.and_then(|signature| keys.verify(&contents, &signature));
+        Ok((file, signer))
+    }
+
+    /// Parses `contents`, read from the checksum file at `path`, as [`ChecksumFile::read`] does.
+    fn from_contents(path: &Path, contents: &[u8], algorithm: Option<HashAlgorithm>) -> Self {
+        let text = String::from_utf8_lossy(contents);
+        if is_sfv(path) {
+            return Self::parse_sfv(&text);
+        }
+        let algorithm = algorithm.or_else(|| algorithm_for_file_name(path));
+        Self::parse(&text, algorithm)
+    }
+
+    /// Hashes every entry, resolving file names relative to `base_dir`.
+    ///
+    /// Several files are hashed at once as configured by [`VerifyOptions::batch`], but the
+    /// results are reported in file order.
+    #[must_use]
+    pub fn verify(&self, base_dir: &Path, options: VerifyOptions) -> VerificationReport {
+        let base_dir = base_dir.to_owned();
+        let entries = self.entries.clone().into_iter();
+        let results = crate::batch::parallel_map(entries, &options.batch, move |entry, token| {
+            let path = base_dir.join(&entry.file_name);
+            let algorithm = entry.digest.algorithm();
+            let hash_options = options.batch.hash_options(token);
+            let status = match crate::hash_file_with(&path, &[algorithm], hash_options) {
+                Ok(hashes) if hashes.get(&algorithm) == Some(&entry.digest) => EntryStatus::Ok,
+                Ok(_) => EntryStatus::Failed,
+                Err(HashError::NotFound { .. }) if options.ignore_missing => return None,
+                Err(HashError::NotFound { .. }) => EntryStatus::Missing,
+                Err(err) => EntryStatus::Unreadable(err),
+            };
+            Some(EntryResult {
+                file_name: entry.file_name,
+                path,
+                algorithm,
+                status,
+            })
+        })
+        .ordered()
+        .flatten()
+        .collect();
+        VerificationReport {
+            results,
+            malformed_lines: self.malformed_lines.clone(),
+            options,
+            signature: None,
+        }
+    }
+}
+
+/// Parses a single non-empty line into its file name, digest and binary flag.
+fn parse_line(line: &str, algorithm: Option<HashAlgorithm>) -> Option<(String, Digest, bool)> {
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (file_name, digest, binary) =
+        parse_tagged(line).or_else(|| parse_untagged(line, algorithm))?;
+    let file_name = if escaped {
+        unescape(file_name)?
+    } else {
+        file_name.to_owned()
+    };
+    Some((file_name, digest, binary))
+}
+
+/// Parses `<ALGORITHM> (<file>) = <hex>`.
+fn parse_tagged(line: &str) -> Option<(&str, Digest, bool)> {
+    let (tag, rest) = line.split_once(" (")?;
+    let (file_name, hex) = rest.rsplit_once(") = ")?;
+    let algorithm = tag.parse().ok()?;
+    Some((file_name, parse_hex(algorithm, hex)?, true))
+}
+
+/// Parses `<hex>  <file>` or `<hex> *<file>`.
+fn parse_untagged(line: &str, algorithm: Option<HashAlgorithm>) -> Option<(&str, Digest, bool)> {
+    let (hex, rest) = line.split_once(' ')?;
+    let binary = match rest.chars().next()? {
+        ' ' => false,
+        '*' => true,
+        _ => return None,
+    };
+    let file_name = &rest[1..];
+    if file_name.is_empty() {
+        return None;
+    }
+    let algorithm = match algorithm {
+        Some(algorithm) => algorithm,
+        None => default_algorithm(hex.len() / 2)?,
+    };
+    Some((file_name, parse_hex(algorithm, hex)?, binary))
+}
+
+/// Parses `<file> <CRC-32>`. The file name may contain spaces.
+fn parse_sfv_line(line: &str) -> Option<(&str, Digest)> {
+    let (file_name, hex) = line.rsplit_once([' ', '\t'])?;
+    let file_name = file_name.trim_end();
+    if file_name.is_empty() {
+        return None;
+    }
+    Some((file_name, parse_hex(HashAlgorithm::Crc32, hex)?))
+}
+
+fn parse_hex(algorithm: HashAlgorithm, hex: &str) -> Option<Digest> {
+    if hex.len() != algorithm.output_len() * 2 {
+        return None;
+    }
+    let bytes = base16ct::mixed::decode_vec(hex).ok()?;
+    Digest::new(algorithm, bytes).ok()
+}
+
+/// Undoes the `\\`, `\n` and `\r` escapes GNU tools apply to unusual file names.
+fn unescape(file_name: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(file_name.len());
+    let mut chars = file_name.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '\\' => unescaped.push('\\'),
+            'n' => unescaped.push('\n'),
+            'r' => unescaped.push('\r'),
+            _ => return None,
+        }
+    }
+    Some(unescaped)
+}
+
+/// Whether `path` names an SFV file.
+fn is_sfv(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("sfv"))
+}
+
+/// Guesses the algorithm of a checksum file from conventional names such as `SHA256SUMS`,
+/// `MD5SUMS`, `B2SUMS`, `image.iso.sha512` or `release.sfv`.
+#[must_use]
+pub fn algorithm_for_file_name(path: &Path) -> Option<HashAlgorithm> {
+    let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+    let name = name.strip_suffix(".txt").unwrap_or(&name);
+    let stem = name
+        .strip_suffix("sums")
+        .or_else(|| name.strip_suffix("sum"));
+    let candidate = match stem {
+        Some(stem) => stem,
+        None => name.rsplit_once('.')?.1,
+    };
+    match candidate {
+        "b2" => Some(HashAlgorithm::Blake2b),
+        "b3" => Some(HashAlgorithm::Blake3),
+        "sfv" => Some(HashAlgorithm::Crc32),
+        _ => candidate.parse().ok(),
+    }
+}
+
+/// How [`ChecksumFile::verify`] treats missing files and malformed lines,
+/// mirroring the `sha256sum -c` flags of the same names.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VerifyOptions {
+    /// Don't fail or report status for files listed in the checksum file that don't exist.
+    pub ignore_missing: bool,
+
+    /// Fail if the checksum file contains improperly formatted lines.
+    pub strict: bool,
+
+    /// How many files to hash at once.
+    pub batch: BatchOptions,
+}
+
+/// The outcome of checking one entry of a checksum file.
+#[derive(Debug)]
+pub enum EntryStatus {
+    /// The file's digest matches.
+    Ok,
+
+    /// The file's digest does not match.
+    Failed,
+
+    /// The file does not exist.
+    Missing,
+
+    /// The file exists but could not be read.
+    Unreadable(HashError),
+}
+
+impl fmt::Display for EntryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ok => f.write_str("OK"),
+            Self::Failed => f.write_str("FAILED"),
+            Self::Missing => f.write_str("MISSING"),
+            Self::Unreadable(_) => f.write_str("FAILED open or read"),
+        }
+    }
+}
+
+/// The result of checking one entry of a checksum file.
+#[derive(Debug)]
+pub struct EntryResult {
+    /// The file name as written in the checksum file.
+    pub file_name: String,
+
+    /// Where the file was looked for.
+    pub path: PathBuf,
+
+    pub algorithm: HashAlgorithm,
+
+    pub status: EntryStatus,
+}
+
+impl fmt::Display for EntryResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.file_name, self.status)
+    }
+}
+
+/// Counts of each outcome in a [`VerificationReport`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub ok: usize,
+    pub failed: usize,
+    pub missing: usize,
+    pub unreadable: usize,
+    pub malformed: usize,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} OK, {} FAILED, {} MISSING, {} unreadable, {} improperly formatted",
+            self.ok, self.failed, self.missing, self.unreadable, self.malformed
+        )
+    }
+}
+
+/// The per-entry results of checking a checksum file, with the overall verdict.
+#[derive(Debug)]
+pub struct VerificationReport {
+    /// The result of every entry that was checked, in file order.
+    pub results: Vec<EntryResult>,
+
+    /// The 1-based line numbers of lines that could not be parsed.
+    pub malformed_lines: Vec<usize>,
+
+    pub options: VerifyOptions,
+
+    /// Who signed the checksum file, if its signature was checked.
+    /// See [`verify_signed_checksum_file`].
+    pub signature: Option<Result<Signer, SignatureError>>,
+}
+
+impl VerificationReport {
+    #[must_use]
+    pub fn summary(&self) -> Summary {
+        let mut summary = Summary {
+            malformed: self.malformed_lines.len(),
+            ..Summary::default()
+        };
+        for result in &self.results {
+            match result.status {
+                EntryStatus::Ok => summary.ok += 1,
+                EntryStatus::Failed => summary.failed += 1,
+                EntryStatus::Missing => summary.missing += 1,
+                EntryStatus::Unreadable(_) => summary.unreadable += 1,
+            }
+        }
+        summary
+    }
+
+    /// Whether verification succeeded by the rules of `sha256sum -c`: every checked file
+    /// matched, at least one file was checked, and with [`VerifyOptions::strict`] every line
+    /// was well-formed. If the signature was checked, it must also be good.
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        let summary = self.summary();
+        summary.ok > 0
+            && summary.failed == 0
+            && summary.missing == 0
+            && summary.unreadable == 0
+            && !(self.options.strict && summary.malformed > 0)
+            && self.signature.as_ref().is_none_or(Result::is_ok)
+    }
+}
+
+/// Reads the checksum file at `path` and verifies every entry relative to its directory.
+///
+/// # Errors
+///
+/// Returns an error if the checksum file itself cannot be read. Problems with the listed files
+/// are reported per entry in the [`VerificationReport`].
+///
+/// # Examples
+///
+/// ```rust
+/// use hash_checker::{VerifyOptions, verify_checksum_file};
+/// let options = VerifyOptions {
+///     ignore_missing: true,
+///     ..VerifyOptions::default()
+/// };
+/// let report = verify_checksum_file("examples/SHA256SUMS", None, options).expect("readable");
+/// for result in &report.results {
+///     println!("{result}");
+/// }
+/// ```
+pub fn verify_checksum_file(
+    path: impl AsRef<Path>,
+    algorithm: Option<HashAlgorithm>,
+    options: VerifyOptions,
+) -> Result<VerificationReport, HashError> {
+    let path = path.as_ref();
+    let file = ChecksumFile::read(path, algorithm)?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    Ok(file.verify(base_dir, options))
+}
+
+/// Like [`verify_checksum_file`], but first checks that the checksum file was signed by one of
+/// `keys`, so a successful report means the files are the ones the signer published.
+///
+/// The signature is found as [`ChecksumFile::read_signed`] does. The outcome is recorded in
+/// [`VerificationReport::signature`]; the files are checked either way.
+///
+/// # Errors
+///
+/// Returns an error if the checksum file itself cannot be read.
+///
+/// # Examples
+///
+/// ```rust
+/// use hash_checker::{TrustedKeys, VerifyOptions, verify_signed_checksum_file};
+/// let keys = TrustedKeys::load("examples/keys".as_ref()).expect("readable");
+/// let report =
+///     verify_signed_checksum_file("examples/SHA256SUMS", None, None, VerifyOptions::default(), &keys)
+///         .expect("readable");
+/// let signer = report.signature.expect("checked").expect("signed by a trusted key");
+/// assert_eq!(signer.name, "release.minisign");
+/// ```
+pub fn verify_signed_checksum_file(
+    path: impl AsRef<Path>,
+    signature: Option<&Path>,
+    algorithm: Option<HashAlgorithm>,
+    options: VerifyOptions,
+    keys: &TrustedKeys,
+) -> Result<VerificationReport, HashError> {
+    let path = path.as_ref();
+    let (file, signer) = ChecksumFile::read_signed(path, signature, algorithm, keys)?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut report = file.verify(base_dir, options);
+    report.signature = Some(signer);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    const VALID_SHA256: &str = "6d78392a5886177fe5b86e585a0b695a2bcd01a05504b3c4e38bc8eeb21e8326";
+
+    #[test]
+    fn test_parse_formats() {
+        let text = format!(
+            "{VALID_SHA256}  plain.txt\n\
+             {VALID_SHA256} *binary.bin\n\
+             \\{VALID_SHA256}  back\\\\slash\\nnewline\n\
+             SHA256 (tagged (1).txt) = {VALID_SHA256}\n\
+             \n\
+             {VALID_SHA256} one-space.txt\n\
+             abcd  too-short.txt\n"
+        );
+        let file = ChecksumFile::parse(&text, None);
+        let names: Vec<&str> = file.entries.iter().map(|e| e.file_name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "plain.txt",
+                "binary.bin",
+                "back\\slash\nnewline",
+                "tagged (1).txt"
+            ]
+        );
+        assert!(!file.entries[0].binary);
+        assert!(file.entries[1].binary);
+        assert_eq!(file.malformed_lines, [6, 7]);
+    }
+
+    #[test]
+    fn test_algorithm_for_file_name() {
+        let cases = [
+            ("SHA256SUMS", Some(HashAlgorithm::Sha256)),
+            ("dist/SHA512SUMS.txt", Some(HashAlgorithm::Sha512)),
+            ("MD5SUMS", Some(HashAlgorithm::Md5)),
+            ("B2SUMS", Some(HashAlgorithm::Blake2b)),
+            ("image.iso.sha1", Some(HashAlgorithm::Sha1)),
+            ("release.SFV", Some(HashAlgorithm::Crc32)),
+            ("CHECKSUMS", None),
+        ];
+        for (name, algorithm) in cases {
+            assert_eq!(
+                algorithm_for_file_name(Path::new(name)),
+                algorithm,
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let report = verify_checksum_file("examples/SHA256SUMS", None, VerifyOptions::default())
+            .expect("Expected SHA256SUMS to be readable.");
+        let statuses: Vec<String> = report.results.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            statuses,
+            ["valid.txt: OK", "valid.txt: FAILED", "missing.txt: MISSING"]
+        );
+        assert_eq!(report.summary().malformed, 1);
+        assert!(!report.is_success());
+    }
+
+    #[test]
+    fn test_sfv() {
+        let file = ChecksumFile::parse_sfv(
+            "; Generated by hand\r\n\
+             ;\r\n\
+             spaced name.iso\t0123ABCD\r\n\
+             valid.txt e0117757\r\n\
+             no-checksum.txt\r\n",
+        );
+        let names: Vec<&str> = file.entries.iter().map(|e| e.file_name.as_str()).collect();
+        assert_eq!(names, ["spaced name.iso", "valid.txt"]);
+        assert_eq!(file.entries[0].digest.to_string(), "0123abcd");
+        assert_eq!(file.malformed_lines, [5]);
+
+        let report = verify_checksum_file("examples/release.sfv", None, VerifyOptions::default())
+            .expect("Expected release.sfv to be readable.");
+        let statuses: Vec<String> = report.results.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            statuses,
+            [
+                "valid.txt: OK",
+                "SHA256SUMS: FAILED",
+                "missing.txt: MISSING"
+            ]
+        );
+        assert!(!report.is_success());
+    }
+
+    #[test]
+    fn test_verify_signed() {
+        let keys = TrustedKeys::load(Path::new("examples/keys")).expect("Expected keys to load.");
+        let options = VerifyOptions {
+            ignore_missing: true,
+            ..VerifyOptions::default()
+        };
+        let signed = ChecksumFile::parse(&format!("{VALID_SHA256}  valid.txt\n"), None);
+        let mut report = signed.verify(Path::new("examples"), options);
+        assert!(report.is_success());
+
+        let mismatched = verify_signed_checksum_file(
+            "examples/release.sfv",
+            Some(Path::new("examples/signatures/ssh.sig")),
+            None,
+            options,
+            &keys,
+        )
+        .expect("Expected release.sfv to be readable.");
+        assert!(matches!(
+            mismatched.signature,
+            Some(Err(SignatureError::Invalid))
+        ));
+
+        report.signature = Some(Err(SignatureError::NotFound {
+            path: PathBuf::from("SHA256SUMS"),
+        }));
+        assert!(!report.is_success(), "a missing signature fails");
+    }
+
+    #[test]
+    fn test_clearsigned() {
+        let keys = TrustedKeys::load(Path::new("examples/keys")).expect("Expected keys to load.");
+        let (file, signer) = ChecksumFile::read_signed("examples/CHECKSUM", None, None, &keys)
+            .expect("Expected CHECKSUM to be readable.");
+        let signer = signer.expect("Expected CHECKSUM to be signed by a trusted key.");
+        assert_eq!(signer.name, "release.openpgp");
+        // Only the signed text is read, and lines are numbered as in the message.
+        let lines: Vec<(usize, &str)> = file
+            .entries
+            .iter()
+            .map(|entry| (entry.line, entry.file_name.as_str()))
+            .collect();
+        assert_eq!(lines, [(4, "valid.txt"), (5, "missing.txt")]);
+        assert!(file.malformed_lines.is_empty());
+    }
+
+    #[test]
+    fn test_clearsigned_is_not_a_detached_signature() {
+        let keys = TrustedKeys::load(Path::new("examples/keys")).expect("Expected keys to load.");
+        let dir = TempDir::new("forged");
+        dir.write("SHA256SUMS", &format!("{}  valid.txt\n", "0".repeat(64)));
+        std::fs::copy("examples/CHECKSUM", dir.0.join("SHA256SUMS.asc"))
+            .expect("Failed to copy a file");
+        let forged = dir.0.join("SHA256SUMS");
+
+        let found = ChecksumFile::read_signed(&forged, None, None, &keys);
+        let explicit =
+            ChecksumFile::read_signed(&forged, Some(Path::new("examples/CHECKSUM")), None, &keys);
+        for result in [found, explicit] {
+            let (_, signer) = result.expect("Expected SHA256SUMS to be readable.");
+            assert!(
+                matches!(signer, Err(SignatureError::Malformed(_))),
+                "got {signer:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify_ignore_missing_and_strict() {
+        let file = ChecksumFile::parse(&format!("{VALID_SHA256}  valid.txt\nbogus\n"), None);
+        let base_dir = Path::new("examples");
+
+        let report = file.verify(base_dir, VerifyOptions::default());
+        assert!(report.is_success(), "malformed lines are only warnings");
+
+        let strict = VerifyOptions {
+            strict: true,
+            ..VerifyOptions::default()
+        };
+        assert!(!file.verify(base_dir, strict).is_success());
+
+        let missing = ChecksumFile::parse(&format!("{VALID_SHA256}  missing.txt\n"), None);
+        let ignore_missing = VerifyOptions {
+            ignore_missing: true,
+            ..VerifyOptions::default()
+        };
+        let report = missing.verify(base_dir, ignore_missing);
+        assert!(report.results.is_empty());
+        assert!(!report.is_success(), "no file was verified");
+    }
+}
@@ -0,0 +1,1112 @@
+//! The command-line interface, used instead of the GUI when arguments are given
+
+use clap::{Args, Parser, Subcommand};
+use hash_checker::{
+    BatchOptions, ChecksumFile, Cid, Comparison, Digest, ExpectedDigest, HashAlgorithm, HashError,
+    Integrity, Manifest, ManifestFormat, ObjectFormat, ReadStrategy, SRI_ALGORITHMS, Signature,
+    SignatureError, TrustedKeys, VerificationReport, VerifyOptions, WalkError, WalkOptions,
+};
+use std::collections::BTreeMap;
+use std::io::{self, Read as _, Write as _};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// Check files against published checksums, or run without arguments to open the app.
+///
+/// Exit status is 0 if every file matched, 1 if any file did not match, and 2 if a file could
+/// not be read or the arguments were invalid.
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(flatten)]
+    output: OutputOptions,
+
+    /// How many files to hash at once [default: one per CPU core].
+    #[arg(short, long, global = true, value_name = "N")]
+    jobs: Option<usize>,
+
+    /// How files are read: buffered, large or mmap. `mmap` needs the `mmap` feature and
+    /// otherwise behaves like `large`.
+    #[arg(long, global = true, value_name = "STRATEGY", default_value_t)]
+    read_strategy: ReadStrategy,
+
+    /// Hash files of at least this many MiB with BLAKE3 on every core [default: 64 when given
+    /// without a size].
+    #[arg(
+        long,
+        global = true,
+        value_name = "MIB",
+        num_args = 0..=1,
+        default_missing_value = "64"
+    )]
+    parallel_blake3: Option<u64>,
+
+    /// The directory of public keys trusted to sign checksum files [default: `trusted-keys` in
+    /// the app's data directory].
+    #[arg(long, global = true, value_name = "DIR")]
+    trusted_keys: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Args, Clone, Copy)]
+struct OutputOptions {
+    /// Print results as JSON.
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Print nothing but errors; use the exit status to tell the result.
+    #[arg(short, long, global = true)]
+    quiet: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the digests of files, optionally comparing them against an expected digest.
+    Hash {
+        /// Files to hash. Directories are hashed recursively. Use `-` to read standard input.
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// The algorithm to hash with. May be given more than once [default: sha256].
+        #[arg(short, long = "algo")]
+        algorithms: Vec<HashAlgorithm>,
+
+        /// The digest every file should have, in hex, Base64 or Base32, optionally labelled
+        /// like `sha256:<hex>`. The algorithm is detected if `--algo` isn't given.
+        #[arg(short, long)]
+        expected: Option<ExpectedDigest>,
+
+        /// Print Subresource Integrity metadata, such as `sha384-<base64>`, for use in HTML
+        /// `integrity` attributes. Only sha256, sha384 and sha512 are allowed [default: sha384].
+        #[arg(long)]
+        sri: bool,
+
+        /// Print a version 1 CID of raw content, such as `bafkrei<base32>`, as used by IPFS and
+        /// other content-addressed stores. Checksums such as crc32 can't be used.
+        #[arg(long, conflicts_with = "sri")]
+        cid: bool,
+
+        #[command(flatten)]
+        walk: WalkArgs,
+    },
+
+    /// Verify files against checksum files such as SHA256SUMS, like `sha256sum -c`.
+    Check {
+        /// Checksum files to read. Use `-` to read standard input.
+        #[arg(required = true)]
+        checksum_files: Vec<PathBuf>,
+
+        /// The algorithm the checksum files use [default: guessed from the file name or digest
+        /// length].
+        #[arg(short, long = "algo")]
+        algorithm: Option<HashAlgorithm>,
+
+        /// Don't fail or report status for missing files.
+        #[arg(long)]
+        ignore_missing: bool,
+
+        /// Fail if a checksum file contains improperly formatted lines.
+        #[arg(long)]
+        strict: bool,
+
+        /// Fail unless the checksum file is signed by a trusted minisign, signify, SSH or OpenPGP
+        /// key. The signature is read from a clearsigned checksum file itself, or else from a
+        /// `.minisig`, `.sig`, `.asc` or `.gpg` file next to it.
+        #[arg(long)]
+        signed: bool,
+
+        /// Read the signature from this file instead. Implies --signed.
+        #[arg(long, value_name = "FILE")]
+        signature: Option<PathBuf>,
+
+        /// Also trust the public key in this file. May be given more than once. Implies --signed.
+        #[arg(long, value_name = "FILE")]
+        key: Vec<PathBuf>,
+    },
+
+    /// Write a checksum manifest, such as SHA256SUMS, for files and directories.
+    Manifest {
+        /// Files to list. Directories are listed recursively.
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// The algorithm to hash with. May be given more than once for the bsd and json formats
+        /// [default: sha256, or crc32 for SFV].
+        #[arg(short, long = "algo")]
+        algorithms: Vec<HashAlgorithm>,
+
+        /// The manifest format: gnu, bsd, json or sfv. `--json` is short for `--format json`.
+        #[arg(short, long, default_value_t)]
+        format: ManifestFormat,
+
+        /// Record paths relative to this directory instead of the current one.
+        #[arg(long, value_name = "DIR")]
+        relative_to: Option<PathBuf>,
+
+        /// Write the manifest to this file instead of standard output.
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        #[command(flatten)]
+        walk: WalkArgs,
+    },
+
+    /// Print the object IDs git would give files (blobs) and directories (trees), without a
+    /// repository.
+    GitId {
+        /// Files and directories to hash. Use `-` to read standard input.
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// The object format of the repository, `sha1` or `sha256` [default: sha256 if
+        /// `--expected` is 64 hex digits long, otherwise sha1].
+        #[arg(long, value_name = "FORMAT")]
+        object_format: Option<ObjectFormat>,
+
+        /// The object ID every path should have, e.g. from `git ls-tree`.
+        #[arg(short, long, value_name = "ID")]
+        expected: Option<String>,
+    },
+
+    /// Manage the public keys trusted to sign checksum files.
+    Keys {
+        #[command(subcommand)]
+        command: KeysCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeysCommand {
+    /// List the trusted keys.
+    List,
+
+    /// Trust the minisign, signify, SSH or OpenPGP public key in a file.
+    Add {
+        file: PathBuf,
+
+        /// The name to trust the key under [default: the file name].
+        #[arg(long)]
+        name: Option<String>,
+    },
+
+    /// Stop trusting a key.
+    Remove { name: String },
+}
+
+/// How directories given on the command line are walked.
+#[derive(Args)]
+struct WalkArgs {
+    /// Only hash files matching this `.gitignore`-style glob. May be given more than once.
+    #[arg(long, value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// Skip files and directories matching this `.gitignore`-style glob. May be given more than
+    /// once.
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Also honour ignore files with this name, such as `.gitignore`. `.hashignore` files are
+    /// always honoured.
+    #[arg(long, value_name = "NAME")]
+    ignore_file: Vec<String>,
+
+    /// Follow symbolic links inside directories.
+    #[arg(short = 'L', long)]
+    follow_symlinks: bool,
+}
+
+impl WalkArgs {
+    fn options(&self) -> WalkOptions {
+        let mut options = WalkOptions::new().follow_symlinks(self.follow_symlinks);
+        for pattern in &self.include {
+            options = options.include(pattern);
+        }
+        for pattern in &self.exclude {
+            options = options.exclude(pattern);
+        }
+        for name in &self.ignore_file {
+            options = options.ignore_file(name);
+        }
+        options
+    }
+}
+
+/// The overall result of a command, from best to worst.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Outcome {
+    Match,
+    Mismatch,
+    Error,
+}
+
+impl From<Outcome> for ExitCode {
+    fn from(outcome: Outcome) -> Self {
+        match outcome {
+            Outcome::Match => Self::SUCCESS,
+            Outcome::Mismatch => Self::from(1),
+            Outcome::Error => Self::from(2),
+        }
+    }
+}
+
+/// Runs the command given on the command line.
+pub fn run() -> ExitCode {
+    let cli = Cli::parse();
+    let mut batch = BatchOptions::new().read_strategy(cli.read_strategy);
+    if let Some(jobs) = cli.jobs {
+        batch = batch.workers(jobs);
+    }
+    if let Some(mib) = cli.parallel_blake3 {
+        batch = batch.parallel_blake3(mib.saturating_mul(1024 * 1024));
+    }
+    let outcome = match cli.command {
+        Command::Hash {
+            files,
+            algorithms,
+            expected,
+            sri,
+            cid,
+            walk,
+        } => {
+            let format = if sri {
+                DigestFormat::Sri
+            } else if cid {
+                DigestFormat::Cid
+            } else {
+                DigestFormat::Hex
+            };
+            hash(
+                &files,
+                algorithms,
+                expected.as_ref(),
+                format,
+                &walk.options(),
+                &batch,
+                cli.output,
+            )
+        }
+        Command::Check {
+            checksum_files,
+            algorithm,
+            ignore_missing,
+            strict,
+            signed,
+            signature,
+            key,
+        } => {
+            let options = VerifyOptions {
+                ignore_missing,
+                strict,
+                batch,
+            };
+            let signing = if signed || signature.is_some() || !key.is_empty() {
+                match Signing::new(signature, &key, cli.trusted_keys, &checksum_files) {
+                    Ok(signing) => Some(signing),
+                    Err(err) => {
+                        eprintln!("error: {err}");
+                        return Outcome::Error.into();
+                    }
+                }
+            } else {
+                None
+            };
+            check(
+                &checksum_files,
+                algorithm,
+                options,
+                signing.as_ref(),
+                cli.output,
+            )
+        }
+        Command::Manifest {
+            paths,
+            algorithms,
+            format,
+            relative_to,
+            output,
+            walk,
+        } => {
+            let format = if cli.output.json {
+                ManifestFormat::Json
+            } else {
+                format
+            };
+            let base_dir = relative_to.unwrap_or_default();
+            manifest(
+                &paths,
+                algorithms,
+                format,
+                &base_dir,
+                &walk.options(),
+                &batch,
+                output,
+            )
+        }
+        Command::GitId {
+            paths,
+            object_format,
+            expected,
+        } => git_id(&paths, object_format, expected.as_deref(), cli.output),
+        Command::Keys { command } => keys(command, cli.trusted_keys, cli.output),
+    };
+    outcome.into()
+}
+
+/// Whether `path` means standard input.
+fn is_stdin(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+#[derive(serde::Serialize)]
+struct HashOutput {
+    path: PathBuf,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    digests: BTreeMap<&'static str, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    integrity: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    cids: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    matches: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// How `hash` prints digests.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DigestFormat {
+    /// Hex, like `sha256sum`.
+    Hex,
+
+    /// Subresource Integrity metadata for HTML `integrity` attributes.
+    Sri,
+
+    /// CIDs of raw content, one per algorithm.
+    Cid,
+}
+
+impl DigestFormat {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Hex => "hex digests",
+            Self::Sri => "Subresource Integrity",
+            Self::Cid => "CIDs",
+        }
+    }
+
+    fn allows(self, algorithm: HashAlgorithm) -> bool {
+        match self {
+            Self::Hex => true,
+            Self::Sri => SRI_ALGORITHMS.contains(&algorithm),
+            Self::Cid => hash_checker::multihash_code(algorithm).is_some(),
+        }
+    }
+
+    /// The Subresource Integrity metadata to print, if that is the format.
+    fn integrity(self, hashes: &BTreeMap<HashAlgorithm, Digest>) -> Option<Integrity> {
+        match self {
+            Self::Sri => Integrity::from_digests(hashes.values()),
+            Self::Hex | Self::Cid => None,
+        }
+    }
+
+    /// The CIDs to print, if that is the format.
+    fn cids(self, hashes: &BTreeMap<HashAlgorithm, Digest>) -> Vec<Cid> {
+        match self {
+            Self::Cid => hashes
+                .values()
+                .filter_map(|digest| Cid::new(digest.clone()).ok())
+                .collect(),
+            Self::Hex | Self::Sri => Vec::new(),
+        }
+    }
+
+    /// Prints the digests of `file` in this format.
+    fn print(self, file: &Path, hashes: &BTreeMap<HashAlgorithm, Digest>) {
+        if self == Self::Hex {
+            print_hashes(file, hashes);
+        }
+        if let Some(integrity) = self.integrity(hashes) {
+            println!("{integrity}  {}", file.display());
+        }
+        for cid in self.cids(hashes) {
+            println!("{cid}  {}", file.display());
+        }
+    }
+
+    /// The algorithm to use when neither `--algo` nor `--expected` says.
+    fn default_algorithm(self) -> HashAlgorithm {
+        match self {
+            Self::Hex | Self::Cid => HashAlgorithm::Sha256,
+            Self::Sri => HashAlgorithm::Sha384,
+        }
+    }
+}
+
+fn hash(
+    files: &[PathBuf],
+    mut algorithms: Vec<HashAlgorithm>,
+    expected: Option<&ExpectedDigest>,
+    format: DigestFormat,
+    walk: &WalkOptions,
+    batch: &BatchOptions,
+    output: OutputOptions,
+) -> Outcome {
+    if let Some(algorithm) = algorithms
+        .iter()
+        .find(|algorithm| !format.allows(**algorithm))
+    {
+        eprintln!("error: {algorithm} can't be used for {}", format.name());
+        return Outcome::Error;
+    }
+    let show_digests = !algorithms.is_empty() || expected.is_none() || format != DigestFormat::Hex;
+    if algorithms.is_empty() {
+        algorithms = expected.map_or_else(Vec::new, ExpectedDigest::algorithms);
+        algorithms.retain(|algorithm| format.allows(*algorithm));
+        if algorithms.is_empty() {
+            algorithms.push(format.default_algorithm());
+        }
+    }
+
+    let mut outcome = Outcome::Match;
+    let mut results = Vec::new();
+    let mut report = |path: PathBuf, hashed: Result<BTreeMap<HashAlgorithm, Digest>, String>| {
+        let hashes = match hashed {
+            Ok(hashes) => hashes,
+            Err(err) => {
+                outcome = outcome.max(Outcome::Error);
+                eprintln!("error: {err}");
+                results.push(HashOutput {
+                    path,
+                    digests: BTreeMap::new(),
+                    integrity: None,
+                    cids: Vec::new(),
+                    matches: None,
+                    error: Some(err),
+                });
+                return;
+            }
+        };
+
+        let comparison = expected.and_then(|expected| expected.compare(&hashes));
+        let matches = expected.map(|_| matches!(comparison, Some(Comparison::Match(_))));
+        if matches == Some(false) {
+            outcome = outcome.max(Outcome::Mismatch);
+        }
+        if !output.json && !output.quiet {
+            // Digests of every candidate algorithm are noise when only the verdict was asked for.
+            if show_digests {
+                format.print(&path, &hashes);
+            }
+            if expected.is_some() {
+                print_comparison(&path, comparison);
+            }
+        }
+        results.push(HashOutput {
+            path,
+            digests: hashes
+                .iter()
+                .map(|(algorithm, digest)| (algorithm.id(), digest.to_hex()))
+                .collect(),
+            integrity: format.integrity(&hashes).as_ref().map(Integrity::to_string),
+            cids: format.cids(&hashes).iter().map(Cid::to_string).collect(),
+            matches,
+            error: None,
+        });
+    };
+
+    // Hash everything between reads of standard input in one batch, so several files are
+    // hashed at once while the output keeps the order of the arguments.
+    for group in files.chunk_by(|a, b| is_stdin(a) == is_stdin(b)) {
+        if is_stdin(&group[0]) {
+            for file in group {
+                let hashed = hash_checker::hash_reader_multi(io::stdin().lock(), &algorithms);
+                report(file.clone(), hashed.map_err(|err| err.to_string()));
+            }
+            continue;
+        }
+        match hash_checker::hash_paths(group.to_vec(), &algorithms, walk, batch) {
+            Ok(hashed) => {
+                for result in hashed {
+                    match result {
+                        Ok((path, hashes)) => report(path, Ok(hashes)),
+                        Err(err) => {
+                            let path = err.path().map(Path::to_owned).unwrap_or_default();
+                            report(path, Err(err.to_string()));
+                        }
+                    }
+                }
+            }
+            Err(err) => report(PathBuf::new(), Err(err.to_string())),
+        }
+    }
+
+    if output.json && !output.quiet {
+        print_json(&results);
+    }
+    outcome
+}
+
+/// Prints digests like `sha256sum`, or like `sha256sum --tag` when there are several.
+fn print_hashes(file: &Path, hashes: &BTreeMap<HashAlgorithm, Digest>) {
+    let file = file.display();
+    if let [(_, digest)] = hashes.iter().collect::<Vec<_>>()[..] {
+        println!("{digest}  {file}");
+        return;
+    }
+    for (algorithm, digest) in hashes {
+        println!("{} ({file}) = {digest}", algorithm.bsd_tag());
+    }
+}
+
+/// Prints whether a file has the expected digest, like `sha256sum -c`.
+fn print_comparison(file: &Path, comparison: Option<Comparison>) {
+    let file = file.display();
+    match comparison {
+        Some(Comparison::Match(algorithm)) if !algorithm.is_cryptographic() => {
+            println!("{file}: OK ({algorithm}, not cryptographic)");
+        }
+        Some(Comparison::Match(algorithm)) => println!("{file}: OK ({algorithm})"),
+        Some(Comparison::Mismatch) | None => println!("{file}: FAILED"),
+    }
+}
+
+#[derive(serde::Serialize)]
+struct CheckOutput {
+    checksum_file: PathBuf,
+    results: Vec<CheckResult>,
+    malformed_lines: Vec<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature: Option<SignatureOutput>,
+    success: bool,
+}
+
+#[derive(serde::Serialize)]
+struct SignatureOutput {
+    valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fingerprint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// The signature `check` requires, and the keys it must be made with.
+struct Signing {
+    /// The signature file, if given explicitly.
+    signature: Option<PathBuf>,
+    keys: TrustedKeys,
+}
+
+impl Signing {
+    fn new(
+        signature: Option<PathBuf>,
+        key_files: &[PathBuf],
+        store: Option<PathBuf>,
+        checksum_files: &[PathBuf],
+    ) -> Result<Self, SignatureError> {
+        if signature.is_some() && checksum_files.len() > 1 {
+            return Err(SignatureError::Malformed(
+                "--signature FILE only works with a single checksum file",
+            ));
+        }
+        let mut keys = match store.or_else(TrustedKeys::default_dir) {
+            Some(dir) => TrustedKeys::load(&dir)?,
+            None => TrustedKeys::new(),
+        };
+        for key_file in key_files {
+            keys.add_file(key_file)?;
+        }
+        Ok(Self { signature, keys })
+    }
+
+    /// Checks the signature over `contents`, read from standard input. Only a clearsigned
+    /// message can be checked without `--signature`.
+    fn verify_stdin(&self, contents: &[u8]) -> Result<hash_checker::Signer, SignatureError> {
+        let signature = match self.signature.as_deref() {
+            Some(path) => Signature::read(path)?,
+            None => Signature::parse_bytes(contents)
+                .ok()
+                .filter(|signature| signature.signed_text().is_some())
+                .ok_or_else(|| SignatureError::NotFound {
+                    path: PathBuf::from("-"),
+                })?,
+        };
+        self.keys.verify(contents, &signature)
+    }
+}
+
+#[derive(serde::Serialize)]
+struct CheckResult {
+    file: String,
+    algorithm: &'static str,
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn check(
+    checksum_files: &[PathBuf],
+    algorithm: Option<HashAlgorithm>,
+    options: VerifyOptions,
+    signing: Option<&Signing>,
+    output: OutputOptions,
+) -> Outcome {
+    let mut outcome = Outcome::Match;
+    let mut outputs = Vec::new();
+    for checksum_file in checksum_files {
+        let report = match read_and_verify(checksum_file, algorithm, options, signing) {
+            Ok(report) => report,
+            Err(err) => {
+                eprintln!("error: {err}");
+                outcome = outcome.max(Outcome::Error);
+                continue;
+            }
+        };
+        let summary = report.summary();
+        if summary.unreadable > 0 || matches!(report.signature, Some(Err(SignatureError::Read(_))))
+        {
+            outcome = outcome.max(Outcome::Error);
+        }
+        if !report.is_success() {
+            outcome = outcome.max(Outcome::Mismatch);
+        }
+
+        if !output.json && !output.quiet {
+            for result in &report.results {
+                println!("{result}");
+            }
+            match &report.signature {
+                Some(Ok(signer)) => println!("{}: signed by {signer}", checksum_file.display()),
+                Some(Err(err)) => println!("{}: signature FAILED: {err}", checksum_file.display()),
+                None => {}
+            }
+            if !report.is_success() || summary.malformed > 0 {
+                eprintln!("{}: {summary}", checksum_file.display());
+            }
+        }
+        outputs.push(check_output(checksum_file, &report));
+    }
+
+    if output.json && !output.quiet {
+        print_json(&outputs);
+    }
+    outcome
+}
+
+fn read_and_verify(
+    checksum_file: &Path,
+    algorithm: Option<HashAlgorithm>,
+    options: VerifyOptions,
+    signing: Option<&Signing>,
+) -> Result<VerificationReport, HashError> {
+    if !is_stdin(checksum_file) {
+        return match signing {
+            Some(signing) => hash_checker::verify_signed_checksum_file(
+                checksum_file,
+                signing.signature.as_deref(),
+                algorithm,
+                options,
+                &signing.keys,
+            ),
+            None => hash_checker::verify_checksum_file(checksum_file, algorithm, options),
+        };
+    }
+    let mut text = Vec::new();
+    io::stdin()
+        .lock()
+        .read_to_end(&mut text)
+        .map_err(|source| HashError::Read {
+            path: None,
+            offset: text.len() as u64,
+            source,
+        })?;
+    let file = ChecksumFile::parse(&String::from_utf8_lossy(&text), algorithm);
+    let mut report = file.verify(Path::new(""), options);
+    report.signature = signing.map(|signing| signing.verify_stdin(&text));
+    Ok(report)
+}
+
+fn check_output(checksum_file: &Path, report: &VerificationReport) -> CheckOutput {
+    CheckOutput {
+        checksum_file: checksum_file.to_owned(),
+        results: report
+            .results
+            .iter()
+            .map(|result| CheckResult {
+                file: result.file_name.clone(),
+                algorithm: result.algorithm.id(),
+                status: result.status.to_string(),
+                error: match &result.status {
+                    hash_checker::EntryStatus::Unreadable(err) => Some(err.to_string()),
+                    _ => None,
+                },
+            })
+            .collect(),
+        malformed_lines: report.malformed_lines.clone(),
+        signature: report.signature.as_ref().map(|signature| match signature {
+            Ok(signer) => SignatureOutput {
+                valid: true,
+                format: Some(signer.format.name()),
+                key: Some(signer.name.clone()),
+                fingerprint: Some(signer.fingerprint.clone()),
+                error: None,
+            },
+            Err(err) => SignatureOutput {
+                valid: false,
+                format: None,
+                key: None,
+                fingerprint: None,
+                error: Some(err.to_string()),
+            },
+        }),
+        success: report.is_success(),
+    }
+}
+
+#[derive(serde::Serialize)]
+struct KeyOutput {
+    name: String,
+    fingerprint: String,
+    comment: String,
+}
+
+fn keys(command: KeysCommand, store: Option<PathBuf>, output: OutputOptions) -> Outcome {
+    let Some(dir) = store.or_else(TrustedKeys::default_dir) else {
+        eprintln!("error: no data directory on this platform; pass --trusted-keys DIR");
+        return Outcome::Error;
+    };
+    let result = match command {
+        KeysCommand::List => TrustedKeys::load(&dir).map(|keys| {
+            let keys: Vec<KeyOutput> = keys
+                .iter()
+                .map(|trusted| KeyOutput {
+                    name: trusted.name.clone(),
+                    fingerprint: trusted.key.fingerprint(),
+                    comment: trusted.key.comment().to_owned(),
+                })
+                .collect();
+            if output.json {
+                print_json(&keys);
+            } else if !output.quiet {
+                for key in keys {
+                    println!("{}  {}  {}", key.name, key.fingerprint, key.comment);
+                }
+            }
+        }),
+        KeysCommand::Add { file, name } => {
+            let name = name.unwrap_or_else(|| {
+                file.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            });
+            TrustedKeys::install(&dir, &name, &file).map(|path| {
+                if !output.quiet {
+                    println!("Trusted {} as {}", file.display(), path.display());
+                }
+            })
+        }
+        KeysCommand::Remove { name } => TrustedKeys::remove(&dir, &name),
+    };
+    match result {
+        Ok(()) => Outcome::Match,
+        Err(err) => {
+            eprintln!("error: {err}");
+            Outcome::Error
+        }
+    }
+}
+
+fn manifest(
+    paths: &[PathBuf],
+    mut algorithms: Vec<HashAlgorithm>,
+    format: ManifestFormat,
+    base_dir: &Path,
+    walk: &WalkOptions,
+    batch: &BatchOptions,
+    output: Option<PathBuf>,
+) -> Outcome {
+    if algorithms.is_empty() {
+        algorithms.push(if format == ManifestFormat::Sfv {
+            HashAlgorithm::Crc32
+        } else {
+            HashAlgorithm::Sha256
+        });
+    }
+    algorithms.sort_unstable();
+    algorithms.dedup();
+    if let Some(algorithm) = algorithms
+        .iter()
+        .find(|algorithm| !format.supports(**algorithm))
+    {
+        eprintln!(
+            "error: {} manifests can't hold {algorithm} digests",
+            format.name()
+        );
+        return Outcome::Error;
+    }
+    if algorithms.len() > 1 && !format.is_tagged() {
+        eprintln!(
+            "error: {} manifests can only hold one algorithm; use --format bsd to list several",
+            format.name()
+        );
+        return Outcome::Error;
+    }
+    let manifest = match hash_manifest(paths, &algorithms, base_dir, walk, batch, output.as_deref())
+    {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            eprintln!("error: {err}");
+            return Outcome::Error;
+        }
+    };
+    let written = if let Some(path) = &output {
+        std::fs::write(path, manifest.render(format))
+    } else {
+        let mut stdout = io::stdout().lock();
+        manifest
+            .write_to(&mut stdout, format)
+            .and_then(|()| stdout.flush())
+    };
+    if let Err(err) = written {
+        let target = output.map_or_else(|| "stdout".to_owned(), |path| path.display().to_string());
+        eprintln!("error: failed to write to {target}: {err}");
+        return Outcome::Error;
+    }
+    Outcome::Match
+}
+
+/// Hashes `paths` into a manifest, leaving out `output` so that a manifest written inside a
+/// directory it lists doesn't list its own previous contents. However `output` is spelled, it is
+/// recognised by comparing canonical paths.
+fn hash_manifest(
+    paths: &[PathBuf],
+    algorithms: &[HashAlgorithm],
+    base_dir: &Path,
+    walk: &WalkOptions,
+    batch: &BatchOptions,
+    output: Option<&Path>,
+) -> Result<Manifest, WalkError> {
+    let output = output.and_then(|path| Some((path.file_name()?, path.canonicalize().ok()?)));
+    let mut manifest = Manifest::new();
+    for result in hash_checker::hash_paths(paths.to_vec(), algorithms, walk, batch)? {
+        let (file, hashes) = result?;
+        if let Some((name, output)) = &output {
+            // Only files with the same name can be the output, which saves canonicalizing the rest.
+            if file.file_name() == Some(*name) && file.canonicalize().ok().as_ref() == Some(output)
+            {
+                continue;
+            }
+        }
+        for digest in hashes.into_values() {
+            manifest.insert(base_dir, &file, digest);
+        }
+    }
+    Ok(manifest)
+}
+
+#[derive(serde::Serialize)]
+struct GitIdOutput {
+    path: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    matches: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn git_id(
+    paths: &[PathBuf],
+    object_format: Option<ObjectFormat>,
+    expected: Option<&str>,
+    output: OutputOptions,
+) -> Outcome {
+    let format = object_format.unwrap_or(match expected {
+        Some(expected) if expected.trim().len() == 64 => ObjectFormat::Sha256,
+        _ => ObjectFormat::Sha1,
+    });
+    let expected = match expected.map(|expected| Digest::parse(format.algorithm(), expected)) {
+        Some(Ok(expected)) => Some(expected),
+        Some(Err(err)) => {
+            eprintln!("error: invalid {format} object ID: {err}");
+            return Outcome::Error;
+        }
+        None => None,
+    };
+
+    let mut outcome = Outcome::Match;
+    let mut results = Vec::new();
+    for path in paths {
+        let id = if is_stdin(path) {
+            let mut data = Vec::new();
+            io::stdin()
+                .lock()
+                .read_to_end(&mut data)
+                .map(|_| hash_checker::git_blob_id_of(&data, format))
+                .map_err(|err| format!("failed to read standard input: {err}"))
+        } else {
+            hash_checker::git_object_id(path, format).map_err(|err| err.to_string())
+        };
+        let id = match id {
+            Ok(id) => id,
+            Err(err) => {
+                outcome = outcome.max(Outcome::Error);
+                eprintln!("error: {err}");
+                results.push(GitIdOutput {
+                    path: path.clone(),
+                    id: None,
+                    matches: None,
+                    error: Some(err),
+                });
+                continue;
+            }
+        };
+        let matches = expected.as_ref().map(|expected| *expected == id);
+        if matches == Some(false) {
+            outcome = outcome.max(Outcome::Mismatch);
+        }
+        if !output.json && !output.quiet {
+            println!("{id}  {}", path.display());
+            if let Some(matches) = matches {
+                let comparison = if matches {
+                    Comparison::Match(format.algorithm())
+                } else {
+                    Comparison::Mismatch
+                };
+                print_comparison(path, Some(comparison));
+            }
+        }
+        results.push(GitIdOutput {
+            path: path.clone(),
+            id: Some(id.to_hex()),
+            matches,
+            error: None,
+        });
+    }
+
+    if output.json && !output.quiet {
+        print_json(&results);
+    }
+    outcome
+}
+
+fn print_json(value: &impl serde::Serialize) {
+    let mut stdout = io::stdout().lock();
+    let written = serde_json::to_writer_pretty(&mut stdout, value)
+        .map_err(io::Error::from)
+        .and_then(|()| writeln!(stdout));
+    if let Err(err) = written {
+        eprintln!("error: failed to write to stdout: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use std::fs;
+
+    const QUIET: OutputOptions = OutputOptions {
+        json: false,
+        quiet: true,
+    };
+
+    /// A directory holding `a.txt` and `sub/b.txt`.
+    fn tree(name: &str) -> TempDir {
+        let dir = TempDir::new(name);
+        dir.write("a.txt", "a\n");
+        dir.write("sub/b.txt", "b\n");
+        dir
+    }
+
+    /// Writes a manifest of the whole directory to `name`, inside it.
+    fn write_manifest(
+        dir: &TempDir,
+        name: &str,
+        algorithms: Vec<HashAlgorithm>,
+        format: ManifestFormat,
+    ) -> Outcome {
+        manifest(
+            &[dir.0.clone()],
+            algorithms,
+            format,
+            &dir.0,
+            &WalkOptions::new(),
+            &BatchOptions::new(),
+            Some(dir.0.join(name)),
+        )
+    }
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let dir = tree("cli-manifest");
+        for (format, name) in [
+            (ManifestFormat::Gnu, "SHA256SUMS"),
+            (ManifestFormat::Bsd, "SHA256SUMS.tag"),
+            (ManifestFormat::Sfv, "checksums.sfv"),
+        ] {
+            // Written twice, so the second time the manifest is already in the tree.
+            for _ in 0..2 {
+                assert_eq!(
+                    write_manifest(&dir, name, Vec::new(), format),
+                    Outcome::Match,
+                    "{format}"
+                );
+            }
+            let sums = dir.0.join(name);
+            let contents = fs::read_to_string(&sums).expect("Failed to read the manifest");
+            assert!(!contents.contains(name), "{format}: {contents}");
+            assert_eq!(
+                check(&[sums], None, VerifyOptions::default(), None, QUIET),
+                Outcome::Match,
+                "{format}"
+            );
+            fs::remove_file(dir.0.join(name)).expect("Failed to remove the manifest");
+        }
+    }
+
+    #[test]
+    fn test_manifest_algorithms() {
+        let dir = tree("cli-manifest-algorithms");
+        let both = vec![HashAlgorithm::Sha256, HashAlgorithm::Sha512];
+        assert_eq!(
+            write_manifest(&dir, "SUMS", both.clone(), ManifestFormat::Gnu),
+            Outcome::Error
+        );
+        assert!(!dir.0.join("SUMS").exists());
+        assert_eq!(
+            write_manifest(
+                &dir,
+                "SUMS",
+                vec![HashAlgorithm::Sha256],
+                ManifestFormat::Sfv
+            ),
+            Outcome::Error
+        );
+
+        // Repeating an algorithm isn't listing several.
+        let twice = vec![HashAlgorithm::Sha512, HashAlgorithm::Sha512];
+        assert_eq!(
+            write_manifest(&dir, "SUMS", twice, ManifestFormat::Gnu),
+            Outcome::Match
+        );
+
+        assert_eq!(
+            write_manifest(&dir, "SUMS", both, ManifestFormat::Bsd),
+            Outcome::Match
+        );
+        let sums = dir.0.join("SUMS");
+        let contents = fs::read_to_string(&sums).expect("Failed to read the manifest");
+        assert_eq!(contents.lines().count(), 4, "{contents}");
+        assert_eq!(
+            check(&[sums], None, VerifyOptions::default(), None, QUIET),
+            Outcome::Match
+        );
+    }
+}
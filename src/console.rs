@@ -0,0 +1,22 @@
+//! Console output for Windows release builds, which are GUI programs without a console of their own
+
+/// Attaches to the console of the process that started us, such as `cmd.exe` or PowerShell, so
+/// the command-line interface can print its results there.
+///
+/// Release builds use the `windows` subsystem so opening the app doesn't flash a console window,
+/// which also means the shell doesn't wait for them to exit. Scripts that need the exit status
+/// should use `start /wait` or `Start-Process -Wait`. Output redirected to a file or pipe
+/// works either way, as the standard handles the shell set up are left alone.
+pub fn attach_to_parent() {
+    // SAFETY: `AttachConsole` takes no pointers, and failing because there is no parent console
+    // or we already have one leaves the process as it was.
+    #[expect(
+        unsafe_code,
+        reason = "the Windows console API is only reachable through FFI"
+    )]
+    let _attached = unsafe {
+        windows_sys::Win32::System::Console::AttachConsole(
+            windows_sys::Win32::System::Console::ATTACH_PARENT_PROCESS,
+        )
+    };
+}
@@ -0,0 +1,297 @@
+//! Typed digest values
+
+use crate::HashAlgorithm;
+use std::fmt;
+use std::str::FromStr;
+use subtle::ConstantTimeEq as _;
+
+/// The output of hashing some data with a [`HashAlgorithm`].
+///
+/// Digests are displayed as lowercase hex, compare in constant time, and can be parsed from
+/// upper- or lowercase hex, Base64 (standard or URL-safe) or Base32.
+///
+/// # Examples
+///
+/// ```rust
+/// use hash_checker::{Digest, HashAlgorithm};
+/// let digest: Digest = "sha256:bXg5KliGF3/luG5YWgtpWivNAaBVBLPE44vI7rIegyY="
+///     .parse()
+///     .expect("valid digest");
+/// assert_eq!(digest.algorithm(), HashAlgorithm::Sha256);
+/// assert_eq!(
+///     digest.to_string(),
+///     "6d78392a5886177fe5b86e585a0b695a2bcd01a05504b3c4e38bc8eeb21e8326"
+/// );
+/// ```
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Digest {
+    algorithm: HashAlgorithm,
+    bytes: Box<[u8]>,
+}
+
+impl Digest {
+    /// Wraps the raw bytes of a digest computed with `algorithm`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseDigestError::Length`] if `bytes` is not as long as the algorithm's output.
+    pub fn new(
+        algorithm: HashAlgorithm,
+        bytes: impl Into<Box<[u8]>>,
+    ) -> Result<Self, ParseDigestError> {
+        let bytes = bytes.into();
+        if bytes.len() == algorithm.output_len() {
+            Ok(Self { algorithm, bytes })
+        } else {
+            Err(ParseDigestError::Length {
+                algorithm,
+                actual: bytes.len(),
+            })
+        }
+    }
+
+    /// Parses an encoded digest that is known to have been computed with `algorithm`.
+    ///
+    /// Valid hex is always read as hex. Anything else is accepted as Base64 or Base32,
+    /// whichever decodes to the algorithm's output length. Surrounding whitespace is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `s` is hex of the wrong length, or not a digest of the right length
+    /// in any supported encoding.
+    pub fn parse(algorithm: HashAlgorithm, s: &str) -> Result<Self, ParseDigestError> {
+        let s = s.trim();
+        if let Ok(bytes) = base16ct::mixed::decode_vec(s) {
+            return Self::new(algorithm, bytes);
+        }
+        let bytes = decode_candidates(s)
+            .find(|bytes| bytes.len() == algorithm.output_len())
+            .ok_or_else(|| ParseDigestError::Encoding(s.to_owned()))?;
+        Self::new(algorithm, bytes)
+    }
+
+    /// The algorithm this digest was computed with.
+    #[must_use]
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// The raw bytes of the digest.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// The digest encoded as lowercase hex. This is the same as its [`fmt::Display`] output.
+    #[must_use]
+    pub fn to_hex(&self) -> String {
+        base16ct::lower::encode_string(&self.bytes)
+    }
+
+    /// The digest encoded as padded standard Base64.
+    #[must_use]
+    pub fn to_base64(&self) -> String {
+        data_encoding::BASE64.encode(&self.bytes)
+    }
+
+    /// The digest encoded as padded uppercase Base32.
+    #[must_use]
+    pub fn to_base32(&self) -> String {
+        data_encoding::BASE32.encode(&self.bytes)
+    }
+
+    /// The digest prefixed with its algorithm, e.g. `sha256:6d78…`, as accepted by [`FromStr`].
+    #[must_use]
+    pub fn to_labelled(&self) -> String {
+        format!("{}:{}", self.algorithm.id(), self.to_hex())
+    }
+}
+
+/// Decodes `s` with every supported encoding, most specific first.
+///
+/// Valid hex is only decoded as hex, since 64 hex digits are also valid Base64 of 48 bytes
+/// and would otherwise pass for a SHA-384 digest.
+pub(crate) fn decode_candidates(s: &str) -> impl Iterator<Item = Vec<u8>> + '_ {
+    let hex = base16ct::mixed::decode_vec(s).ok();
+    let is_hex = hex.is_some();
+    let base64 = [
+        data_encoding::BASE64,
+        data_encoding::BASE64_NOPAD,
+        data_encoding::BASE64URL,
+        data_encoding::BASE64URL_NOPAD,
+    ]
+    .into_iter()
+    .filter(move |_| !is_hex)
+    .filter_map(move |encoding| encoding.decode(s.as_bytes()).ok());
+    let upper = s.to_ascii_uppercase();
+    let base32 = [data_encoding::BASE32, data_encoding::BASE32_NOPAD]
+        .into_iter()
+        .filter(move |_| !is_hex)
+        .filter_map(move |encoding| encoding.decode(upper.as_bytes()).ok());
+    hex.into_iter().chain(base64).chain(base32)
+}
+
+/// The algorithm assumed for an unlabelled digest of the given length.
+pub(crate) fn default_algorithm(len: usize) -> Option<HashAlgorithm> {
+    match len {
+        16 => Some(HashAlgorithm::Md5),
+        20 => Some(HashAlgorithm::Sha1),
+        28 => Some(HashAlgorithm::Sha224),
+        32 => Some(HashAlgorithm::Sha256),
+        48 => Some(HashAlgorithm::Sha384),
+        64 => Some(HashAlgorithm::Sha512),
+        _ => None,
+    }
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl fmt::Debug for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_labelled())
+    }
+}
+
+impl FromStr for Digest {
+    type Err = ParseDigestError;
+
+    /// Parses a digest either labelled with its algorithm (`sha256:6d78…`) or bare.
+    ///
+    /// A bare digest is assumed to be MD5, SHA-1, SHA-224, SHA-256, SHA-384 or SHA-512
+    /// depending on its decoded length.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some((algorithm, encoded)) = s.split_once(':') {
+            let algorithm = algorithm
+                .parse()
+                .map_err(|_unknown| ParseDigestError::Algorithm(algorithm.to_owned()))?;
+            return Self::parse(algorithm, encoded);
+        }
+        decode_candidates(s)
+            .find_map(|bytes| Self::new(default_algorithm(bytes.len())?, bytes).ok())
+            .ok_or_else(|| ParseDigestError::Encoding(s.to_owned()))
+    }
+}
+
+impl PartialEq for Digest {
+    /// Compares digests in constant time with respect to their contents.
+    fn eq(&self, other: &Self) -> bool {
+        self.algorithm == other.algorithm && bool::from(self.bytes.ct_eq(&other.bytes))
+    }
+}
+
+impl Eq for Digest {}
+
+impl From<Digest> for String {
+    fn from(digest: Digest) -> Self {
+        digest.to_labelled()
+    }
+}
+
+impl TryFrom<String> for Digest {
+    type Error = ParseDigestError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// The error returned when a [`Digest`] cannot be parsed or constructed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseDigestError {
+    /// The algorithm label before the `:` was not recognised.
+    Algorithm(String),
+
+    /// The string is not valid hex, Base64 or Base32 of a known digest length.
+    Encoding(String),
+
+    /// The digest bytes do not match the output length of the algorithm.
+    Length {
+        algorithm: HashAlgorithm,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for ParseDigestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Algorithm(name) => write!(f, "unknown hash algorithm: {name}"),
+            Self::Encoding(s) => write!(f, "not a hex, Base64 or Base32 digest: {s}"),
+            Self::Length { algorithm, actual } => write!(
+                f,
+                "{algorithm} digests are {} bytes long, got {actual}",
+                algorithm.output_len()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseDigestError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHA256_HEX: &str = "6d78392a5886177fe5b86e585a0b695a2bcd01a05504b3c4e38bc8eeb21e8326";
+
+    #[test]
+    fn test_parse_encodings() {
+        let expected = Digest::parse(HashAlgorithm::Sha256, SHA256_HEX).expect("valid hex");
+        for encoded in [
+            SHA256_HEX.to_owned(),
+            SHA256_HEX.to_ascii_uppercase(),
+            expected.to_base64(),
+            expected.to_base64().trim_end_matches('=').to_owned(),
+            expected.to_base32(),
+            expected.to_base32().to_ascii_lowercase(),
+        ] {
+            let digest = Digest::parse(HashAlgorithm::Sha256, &encoded).expect("valid encoding");
+            assert_eq!(digest, expected, "decoding {encoded}");
+        }
+    }
+
+    #[test]
+    fn test_hex_is_only_hex() {
+        // 64 hex digits are also Base64 of 48 bytes, the length of a SHA-384 digest.
+        assert_eq!(
+            Digest::parse(HashAlgorithm::Sha384, SHA256_HEX),
+            Err(ParseDigestError::Length {
+                algorithm: HashAlgorithm::Sha384,
+                actual: 32,
+            })
+        );
+        assert!(format!("sha384:{SHA256_HEX}").parse::<Digest>().is_err());
+        let bare: Digest = SHA256_HEX.parse().expect("valid bare digest");
+        assert_eq!(bare.algorithm(), HashAlgorithm::Sha256);
+    }
+
+    #[test]
+    fn test_from_str() {
+        let bare: Digest = SHA256_HEX.parse().expect("valid bare digest");
+        assert_eq!(bare.algorithm(), HashAlgorithm::Sha256);
+        assert_eq!(bare.to_string(), SHA256_HEX);
+
+        let labelled: Digest = format!("blake3:{SHA256_HEX}")
+            .parse()
+            .expect("valid digest");
+        assert_eq!(labelled.algorithm(), HashAlgorithm::Blake3);
+        assert_ne!(labelled, bare, "digests of different algorithms differ");
+
+        assert!("sha256:abcd".parse::<Digest>().is_err());
+        assert!("nope:abcd".parse::<Digest>().is_err());
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let digest: Digest = SHA256_HEX.parse().expect("valid digest");
+        let json = serde_json::to_string(&digest).expect("serializable");
+        assert_eq!(json, format!("\"sha256:{SHA256_HEX}\""));
+        let back: Digest = serde_json::from_str(&json).expect("deserializable");
+        assert_eq!(back, digest);
+    }
+}
@@ -0,0 +1,225 @@
+//! Comparing files against published digests of unknown algorithm
+
+use crate::digest::decode_candidates;
+use crate::{Cid, Digest, HashAlgorithm, HashError, Integrity, ParseDigestError};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+
+/// A digest pasted by the user, together with every algorithm it could plausibly be from.
+///
+/// Vendors rarely say which algorithm a published checksum uses, so the algorithm is inferred
+/// from the digest's encoding and length:
+///
+/// * labelled digests such as `sha256:6d78…` use the labelled algorithm,
+/// * Subresource Integrity strings such as `sha384-SGTk…` use the strongest SHA-2 algorithm
+///   they list; see [`Integrity`],
+/// * CIDs such as `bafkrei…` use the algorithm of their multihash; see [`Cid`],
+/// * bare digests are decoded as hex, Base64 or Base32 and match every algorithm with that
+///   output length, e.g. 32 hex digits are MD5 while 64 could be SHA-256, SHA-512/256,
+///   SHA3-256, BLAKE2s or BLAKE3.
+///
+/// # Examples
+///
+/// ```rust
+/// use hash_checker::{ExpectedDigest, HashAlgorithm};
+/// let expected: ExpectedDigest = "b2cfa4183267af678ea06c7407d4d6d8".parse().expect("valid digest");
+/// assert_eq!(expected.algorithms(), vec![HashAlgorithm::Md5]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpectedDigest {
+    candidates: Vec<Digest>,
+}
+
+/// The result of comparing an [`ExpectedDigest`] against computed digests.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    /// The digest computed with this algorithm equals the expected digest.
+    Match(HashAlgorithm),
+
+    /// None of the candidate algorithms produced the expected digest.
+    Mismatch,
+}
+
+impl ExpectedDigest {
+    /// The algorithms the expected digest could have been computed with, most common first.
+    #[must_use]
+    pub fn algorithms(&self) -> Vec<HashAlgorithm> {
+        self.candidates.iter().map(Digest::algorithm).collect()
+    }
+
+    /// Compares against digests computed for a file.
+    ///
+    /// Returns `None` if none of the candidate algorithms were computed, so no verdict can be given.
+    #[must_use]
+    pub fn compare(&self, hashes: &BTreeMap<HashAlgorithm, Digest>) -> Option<Comparison> {
+        let mut compared = false;
+        for candidate in &self.candidates {
+            if let Some(digest) = hashes.get(&candidate.algorithm()) {
+                if digest == candidate {
+                    return Some(Comparison::Match(candidate.algorithm()));
+                }
+                compared = true;
+            }
+        }
+        compared.then_some(Comparison::Mismatch)
+    }
+}
+
+impl FromStr for ExpectedDigest {
+    type Err = ParseDigestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some((prefix, encoded)) = s.split_once('-') {
+            let algorithm = match prefix {
+                "sha256" => Some(HashAlgorithm::Sha256),
+                "sha384" => Some(HashAlgorithm::Sha384),
+                "sha512" => Some(HashAlgorithm::Sha512),
+                _ => None,
+            };
+            if let Some(algorithm) = algorithm {
+                // Only the strongest algorithm of integrity metadata counts, as in browsers.
+                let candidates = match s.parse::<Integrity>() {
+                    Ok(integrity) => integrity.strongest().cloned().collect(),
+                    Err(_not_sri) => vec![Digest::parse(algorithm, encoded)?],
+                };
+                return Ok(Self { candidates });
+            }
+        }
+        if let Ok(cid) = s.parse::<Cid>() {
+            let candidates = vec![cid.digest().clone()];
+            return Ok(Self { candidates });
+        }
+        if s.contains(':') {
+            let candidates = vec![s.parse()?];
+            return Ok(Self { candidates });
+        }
+        decode_candidates(s)
+            .find_map(|bytes| {
+                let mut algorithms: Vec<HashAlgorithm> = HashAlgorithm::ALL
+                    .into_iter()
+                    .filter(|algorithm| algorithm.output_len() == bytes.len())
+                    .collect();
+                // Checksums are only guessed when no cryptographic hash is this long, so that
+                // a forged XXH3-128 can't stand in for a published MD5.
+                if algorithms
+                    .iter()
+                    .any(|algorithm| algorithm.is_cryptographic())
+                {
+                    algorithms.retain(|algorithm| algorithm.is_cryptographic());
+                }
+                let candidates: Vec<Digest> = algorithms
+                    .into_iter()
+                    .filter_map(|algorithm| Digest::new(algorithm, bytes.clone()).ok())
+                    .collect();
+                (!candidates.is_empty()).then_some(Self { candidates })
+            })
+            .ok_or_else(|| ParseDigestError::Encoding(s.to_owned()))
+    }
+}
+
+/// Hashes the file at `path` with every candidate algorithm of `expected` in a single pass,
+/// and reports whether any of them matched.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be hashed; see [`crate::hash_file_multi`].
+///
+/// # Examples
+///
+/// ```rust
+/// use hash_checker::{Comparison, ExpectedDigest, HashAlgorithm, verify_file};
+/// let expected: ExpectedDigest = "044a2cfbc4e8143f285228036c46d901b892ec940e611820549218a4a7874a2e"
+///     .parse()
+///     .expect("valid digest");
+/// let comparison = verify_file("examples/valid.txt", &expected).expect("valid.txt is readable");
+/// assert_eq!(comparison, Comparison::Match(HashAlgorithm::Sha3_256));
+/// ```
+pub fn verify_file(
+    path: impl AsRef<Path>,
+    expected: &ExpectedDigest,
+) -> Result<Comparison, HashError> {
+    let hashes = crate::hash_file_multi(path, &expected.algorithms())?;
+    Ok(expected
+        .compare(&hashes)
+        .expect("every candidate algorithm was computed"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_by_length() {
+        let cases = [
+            ("b2cfa4183267af678ea06c7407d4d6d8", vec![HashAlgorithm::Md5]),
+            (
+                "179c94cf45c6e383baf52621687305204cef16f9",
+                vec![HashAlgorithm::Sha1],
+            ),
+            (
+                "6D78392A5886177FE5B86E585A0B695A2BCD01A05504B3C4E38BC8EEB21E8326",
+                vec![
+                    HashAlgorithm::Sha256,
+                    HashAlgorithm::Sha512_256,
+                    HashAlgorithm::Sha3_256,
+                    HashAlgorithm::Blake2s,
+                    HashAlgorithm::Blake3,
+                ],
+            ),
+            (
+                "sha384-SOQr6enzDPifQZ6dtwrv+LmoJsz2YINqM6kxPhcCOqsDFT7nv6kPOFmhjonAMBOq",
+                vec![HashAlgorithm::Sha384],
+            ),
+            (
+                "sha256-bXg5KliGF3/luG5YWgtpWivNAaBVBLPE44vI7rIegyY= \
+                 sha384-SOQr6enzDPifQZ6dtwrv+LmoJsz2YINqM6kxPhcCOqsDFT7nv6kPOFmhjonAMBOq",
+                vec![HashAlgorithm::Sha384],
+            ),
+            (
+                "bafkreidnpa4suwegc576lodolbnaw2k2fpgqdicvasz4jy4lzdxlehudey",
+                vec![HashAlgorithm::Sha256],
+            ),
+            (
+                "blake3:368fe3d7b7d7f3fa0c99f90c847ef0297c2b6d072c814ab4eac2f0b2cd9096e5",
+                vec![HashAlgorithm::Blake3],
+            ),
+            (
+                "e0117757",
+                vec![
+                    HashAlgorithm::Crc32,
+                    HashAlgorithm::Crc32c,
+                    HashAlgorithm::Adler32,
+                ],
+            ),
+            (
+                "xxh128:b2cfa4183267af678ea06c7407d4d6d8",
+                vec![HashAlgorithm::Xxh3_128],
+            ),
+        ];
+        for (s, algorithms) in cases {
+            let expected: ExpectedDigest = s.parse().expect("valid digest");
+            assert_eq!(expected.algorithms(), algorithms, "detecting {s}");
+        }
+        assert!("not a digest".parse::<ExpectedDigest>().is_err());
+    }
+
+    #[test]
+    fn test_verify_file() {
+        let expected: ExpectedDigest =
+            "368fe3d7b7d7f3fa0c99f90c847ef0297c2b6d072c814ab4eac2f0b2cd9096e5"
+                .parse()
+                .expect("valid digest");
+        let comparison =
+            verify_file("examples/valid.txt", &expected).expect("valid.txt is readable");
+        assert_eq!(comparison, Comparison::Match(HashAlgorithm::Blake3));
+
+        let expected: ExpectedDigest = "00000000000000000000000000000000"
+            .parse()
+            .expect("valid digest");
+        let comparison =
+            verify_file("examples/valid.txt", &expected).expect("valid.txt is readable");
+        assert_eq!(comparison, Comparison::Mismatch);
+    }
+}
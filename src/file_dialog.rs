@@ -0,0 +1,160 @@
+//! File and folder picker dialogs
+
+use crate::JobSource;
+use std::path::PathBuf;
+use std::sync::mpsc;
+
+/// Shows file pickers and collects what the user picked.
+///
+/// On native targets this uses the platform's dialogs, which block until the user has chosen.
+/// On the web it falls back to an `<input type=file>` element and reads the picked files into
+/// memory in the background, so call [`FileDialogs::poll`] once per frame to collect them.
+pub(crate) struct FileDialogs {
+    sender: mpsc::Sender<JobSource>,
+    receiver: mpsc::Receiver<JobSource>,
+}
+
+impl Default for FileDialogs {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self { sender, receiver }
+    }
+}
+
+impl FileDialogs {
+    /// Lets the user pick one or more files to hash.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn open_files(&self, _ctx: &egui::Context) -> Vec<PathBuf> {
+        let paths = rfd::FileDialog::new().pick_files().unwrap_or_default();
+        for path in &paths {
+            self.sender.send(JobSource::Path(path.clone())).ok();
+        }
+        paths
+    }
+
+    /// Lets the user pick one or more files to hash.
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) fn open_files(&self, ctx: &egui::Context) -> Vec<PathBuf> {
+        let sender = self.sender.clone();
+        let ctx = ctx.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            for file in rfd::AsyncFileDialog::new()
+                .pick_files()
+                .await
+                .unwrap_or_default()
+            {
+                let source = JobSource::Bytes {
+                    name: file.file_name(),
+                    data: file.read().await.into(),
+                };
+                sender.send(source).ok();
+            }
+            ctx.request_repaint();
+        });
+
+        // The web has no paths to remember.
+        Vec::new()
+    }
+
+    /// Lets the user pick a folder and queues every file under it.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn open_folder(&self, ctx: &egui::Context) -> Option<PathBuf> {
+        let folder = rfd::FileDialog::new().pick_folder()?;
+        self.queue_path(ctx, folder.clone());
+        Some(folder)
+    }
+
+    /// Queues `path`, or every file under it if it is a folder. Folders are walked on a
+    /// background thread, so a large tree doesn't freeze the window.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn queue_path(&self, ctx: &egui::Context, path: PathBuf) {
+        if !path.is_dir() {
+            self.sender.send(JobSource::Path(path)).ok();
+            return;
+        }
+        let sender = self.sender.clone();
+        let ctx = ctx.clone();
+        std::thread::Builder::new()
+            .name("walk-folder".to_owned())
+            .spawn(move || {
+                let files = match crate::walk(&path, &crate::WalkOptions::default()) {
+                    Ok(files) => files,
+                    Err(err) => {
+                        log::warn!("Failed to list {}: {err}", path.display());
+                        return;
+                    }
+                };
+                for file in files {
+                    match file {
+                        Ok(file) => {
+                            if sender.send(JobSource::Path(file)).is_err() {
+                                return;
+                            }
+                            ctx.request_repaint();
+                        }
+                        Err(err) => log::warn!("Skipping {err}"),
+                    }
+                }
+            })
+            .expect("Failed to spawn folder walking thread");
+    }
+
+    /// Returns every file picked since the last call.
+    pub(crate) fn poll(&self) -> Vec<JobSource> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+/// Lets the user pick a single file to read rather than hash, such as a checksum file.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn pick_file(title: &str) -> Option<PathBuf> {
+    rfd::FileDialog::new().set_title(title).pick_file()
+}
+
+/// Lets the user choose where to save `contents`, suggesting `file_name`.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn save_file(_ctx: &egui::Context, file_name: &str, contents: &str) {
+    let Some(path) = rfd::FileDialog::new().set_file_name(file_name).save_file() else {
+        return;
+    };
+    if let Err(err) = std::fs::write(&path, contents) {
+        log::warn!("Failed to write {}: {err}", path.display());
+    }
+}
+
+/// Copies `contents` to the clipboard, since web pages can't write files.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn save_file(ctx: &egui::Context, _file_name: &str, contents: &str) {
+    ctx.copy_text(contents.to_owned());
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_queue_folder() {
+        let ctx = egui::Context::default();
+        let dialogs = FileDialogs::default();
+        dialogs.queue_path(&ctx, PathBuf::from("examples"));
+
+        let expected = crate::walk("examples", &crate::WalkOptions::default())
+            .expect("Expected examples to be listed.")
+            .count();
+        let mut queued = Vec::new();
+        for _ in 0..1000 {
+            queued.extend(dialogs.poll());
+            if queued.len() == expected {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(queued.len(), expected);
+        assert!(
+            queued
+                .iter()
+                .any(|source| source.name().ends_with("valid.txt"))
+        );
+    }
+}
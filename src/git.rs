@@ -0,0 +1,317 @@
+//! Git object IDs of files and directories, computed without a repository
+
+use crate::hashing::hash_file_with_header;
+use crate::{Digest, HashAlgorithm, HashError, HashOptions, hash_bytes};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The hash function a git repository names its objects with, as chosen by
+/// `git init --object-format`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ObjectFormat {
+    /// 40 hex digit SHA-1 object IDs, used by nearly every repository.
+    #[default]
+    Sha1,
+
+    /// 64 hex digit SHA-256 object IDs.
+    Sha256,
+}
+
+impl ObjectFormat {
+    pub const ALL: [Self; 2] = [Self::Sha1, Self::Sha256];
+
+    /// The algorithm object IDs are computed with.
+    #[must_use]
+    pub fn algorithm(self) -> HashAlgorithm {
+        match self {
+            Self::Sha1 => HashAlgorithm::Sha1,
+            Self::Sha256 => HashAlgorithm::Sha256,
+        }
+    }
+}
+
+impl fmt::Display for ObjectFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.algorithm().id())
+    }
+}
+
+impl FromStr for ObjectFormat {
+    type Err = UnknownObjectFormat;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.algorithm().id() == s.to_ascii_lowercase())
+            .ok_or_else(|| UnknownObjectFormat(s.to_owned()))
+    }
+}
+
+/// The error returned when parsing an unrecognised [`ObjectFormat`] name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownObjectFormat(pub String);
+
+impl fmt::Display for UnknownObjectFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown git object format: {} (expected sha1 or sha256)",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for UnknownObjectFormat {}
+
+/// The modes git records for the entries of a tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    File,
+    Executable,
+    Symlink,
+    Tree,
+}
+
+impl Mode {
+    fn octal(self) -> &'static str {
+        match self {
+            Self::File => "100644",
+            Self::Executable => "100755",
+            Self::Symlink => "120000",
+            Self::Tree => "40000",
+        }
+    }
+}
+
+/// One line of a tree object.
+struct TreeEntry {
+    name: Vec<u8>,
+    mode: Mode,
+    id: Digest,
+}
+
+impl TreeEntry {
+    /// Git sorts trees as if their names ended in `/`, so `lib-old` and `lib.rs` come before
+    /// the directory `lib`.
+    fn sort_key(&self) -> impl Iterator<Item = &u8> {
+        let suffix: &[u8] = if self.mode == Mode::Tree { b"/" } else { b"" };
+        self.name.iter().chain(suffix)
+    }
+}
+
+/// The ID of the git object of `kind` holding `data`.
+fn object_id(kind: &str, data: &[u8], format: ObjectFormat) -> Digest {
+    let mut object = format!("{kind} {}\0", data.len()).into_bytes();
+    object.extend_from_slice(data);
+    hash_bytes(&object, format.algorithm())
+}
+
+/// The blob ID git would give `data`, as printed by `git hash-object --stdin`.
+///
+/// # Examples
+///
+/// ```rust
+/// use hash_checker::{ObjectFormat, git_blob_id_of};
+/// assert_eq!(
+///     git_blob_id_of(b"", ObjectFormat::Sha1).to_string(),
+///     "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391"
+/// );
+/// ```
+#[must_use]
+pub fn git_blob_id_of(data: &[u8], format: ObjectFormat) -> Digest {
+    object_id("blob", data, format)
+}
+
+/// The blob ID git would give the file at `path`, as printed by `git hash-object`.
+///
+/// The file is streamed, so it needn't fit in memory. Unlike `git hash-object`, no
+/// `.gitattributes` filters or line ending conversions are applied.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read, or if it changes length while being read.
+///
+/// # Examples
+///
+/// ```rust
+/// use hash_checker::{ObjectFormat, git_blob_id};
+/// let id = git_blob_id("examples/valid.txt", ObjectFormat::Sha1).expect("valid.txt is readable");
+/// assert_eq!(id.to_string(), "28d14454c380441fa2b6f562072a1cd641c06e22");
+/// ```
+pub fn git_blob_id(path: impl AsRef<Path>, format: ObjectFormat) -> Result<Digest, HashError> {
+    hash_file_with_header(
+        path.as_ref(),
+        format.algorithm(),
+        |len| format!("blob {len}\0").into_bytes(),
+        HashOptions::default(),
+    )
+}
+
+/// The tree ID git would give the directory at `path`, as printed by `git write-tree` after
+/// adding everything in it.
+///
+/// Regular files, executables, symbolic links and subdirectories are included, except `.git`
+/// and directories with no files in them, which git doesn't track. Ignore files are not
+/// consulted, nested repositories are hashed like any other directory rather than as
+/// submodules, and other kinds of file such as sockets are skipped.
+///
+/// # Errors
+///
+/// Returns an error if any directory or file beneath `path` cannot be read.
+pub fn git_tree_id(path: impl AsRef<Path>, format: ObjectFormat) -> Result<Digest, HashError> {
+    Ok(tree_id(path.as_ref(), format)?.unwrap_or_else(|| object_id("tree", b"", format)))
+}
+
+/// The ID of the tree or blob git would store for `path`, depending on whether it is a
+/// directory.
+///
+/// # Errors
+///
+/// Returns an error if `path` or anything beneath it cannot be read.
+pub fn git_object_id(path: impl AsRef<Path>, format: ObjectFormat) -> Result<Digest, HashError> {
+    let path = path.as_ref();
+    let metadata = fs::metadata(path).map_err(|err| HashError::open(path, err))?;
+    if metadata.is_dir() {
+        git_tree_id(path, format)
+    } else {
+        git_blob_id(path, format)
+    }
+}
+
+/// The ID of the tree for `dir`, or `None` if git would not record it for lack of files.
+fn tree_id(dir: &Path, format: ObjectFormat) -> Result<Option<Digest>, HashError> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|err| HashError::open(dir, err))? {
+        let entry = entry.map_err(|err| HashError::open(dir, err))?;
+        let name = entry.file_name();
+        if name == ".git" {
+            continue;
+        }
+        let path = entry.path();
+        let metadata = fs::symlink_metadata(&path).map_err(|err| HashError::open(&path, err))?;
+        let file_type = metadata.file_type();
+        let (mode, id) = if file_type.is_symlink() {
+            let target = fs::read_link(&path).map_err(|err| HashError::open(&path, err))?;
+            let target = target.as_os_str().as_encoded_bytes();
+            (Mode::Symlink, git_blob_id_of(target, format))
+        } else if file_type.is_dir() {
+            match tree_id(&path, format)? {
+                Some(id) => (Mode::Tree, id),
+                None => continue,
+            }
+        } else if file_type.is_file() {
+            let mode = if is_executable(&metadata) {
+                Mode::Executable
+            } else {
+                Mode::File
+            };
+            (mode, git_blob_id(&path, format)?)
+        } else {
+            continue;
+        };
+        entries.push(TreeEntry {
+            name: name.as_encoded_bytes().to_vec(),
+            mode,
+            id,
+        });
+    }
+    if entries.is_empty() {
+        return Ok(None);
+    }
+
+    entries.sort_by(|a, b| a.sort_key().cmp(b.sort_key()));
+    let mut data = Vec::new();
+    for entry in &entries {
+        data.extend_from_slice(entry.mode.octal().as_bytes());
+        data.push(b' ');
+        data.extend_from_slice(&entry.name);
+        data.push(0);
+        data.extend_from_slice(entry.id.as_bytes());
+    }
+    Ok(Some(object_id("tree", &data, format)))
+}
+
+/// Whether git would record the file as executable, going by the owner's execute bit.
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt as _;
+    metadata.permissions().mode() & 0o100 != 0
+}
+
+/// Whether git would record the file as executable. Other platforms have no execute bit, so
+/// git's `core.fileMode` is off there and files are never executable.
+#[cfg(not(unix))]
+fn is_executable(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_blob_ids() {
+        // As printed by `git hash-object`, in a repository of each object format.
+        assert_eq!(
+            git_blob_id("examples/valid.txt", ObjectFormat::Sha256)
+                .expect("valid.txt is readable")
+                .to_string(),
+            "839b23bd8eea83678f4357a236ff1e932558ba81a9fde1e554921066f86bb711"
+        );
+        assert_eq!(
+            git_blob_id_of(b"old\n", ObjectFormat::Sha1).to_string(),
+            "3367afdbbf91e638efe983616377c60477cc6612"
+        );
+        assert!(matches!(
+            git_blob_id("examples", ObjectFormat::Sha1),
+            Err(HashError::IsADirectory { .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_tree_ids() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let dir = TempDir::new("git-tree");
+        dir.write("lib.rs", "pub mod lib;\n");
+        dir.write("lib-old", "old\n");
+        dir.write("lib/mod.rs", "fn main() {}\n");
+        dir.write("run.sh", "#!/bin/sh\n");
+        fs::set_permissions(dir.0.join("run.sh"), fs::Permissions::from_mode(0o755))
+            .expect("Failed to make a file executable");
+        std::os::unix::fs::symlink("lib.rs", dir.0.join("link"))
+            .expect("Failed to create a symlink");
+        fs::create_dir_all(dir.0.join("empty/nested")).expect("Failed to create a directory");
+        dir.write(".git/HEAD", "ref: refs/heads/main\n");
+
+        // As printed by `git add -A && git write-tree`, in a repository of each object format.
+        assert_eq!(
+            git_object_id(&dir.0, ObjectFormat::Sha1)
+                .expect("readable")
+                .to_string(),
+            "15338af50d372c5b93cc4db0ba221bc529cb3e4d"
+        );
+        assert_eq!(
+            git_object_id(&dir.0, ObjectFormat::Sha256)
+                .expect("readable")
+                .to_string(),
+            "2c40bc593b707dff12ca3e3365ff260271c596696f5760879ccbc11acea2e4b2"
+        );
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let dir = TempDir::new("git-empty-tree");
+        fs::create_dir_all(dir.0.join("a/b")).expect("Failed to create a directory");
+        assert_eq!(
+            git_tree_id(&dir.0, ObjectFormat::Sha1)
+                .expect("readable")
+                .to_string(),
+            "4b825dc642cb6eb9a060e54bf8d69288fbee4904"
+        );
+    }
+}
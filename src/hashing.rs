@@ -1,26 +1,1082 @@
 //! Hashing utilities
 
+use crate::Digest;
 use log::debug;
-use sha2::Sha256;
 use sha2::Digest as _;
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs::File;
 use std::io;
-use std::io::BufReader;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use web_time::Instant;
 
-/// Computes the SHA-256 hash of the contents of a file at the given path and returns the result as a Base64-encoded string.
+/// A hash algorithm that can be used to compute the digest of a file.
+///
+/// Besides cryptographic hashes this includes checksums such as CRC-32 and xxHash, which catch
+/// accidental corruption but are trivial to forge. See [`HashAlgorithm::is_cryptographic`].
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Deserialize, serde::Serialize,
+)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+    Sha512_256,
+    Sha3_256,
+    Sha3_512,
+    Blake2b,
+    Blake2s,
+    Blake3,
+    Crc32,
+    Crc32c,
+    Adler32,
+    Xxh64,
+    Xxh3_64,
+    Xxh3_128,
+}
+
+impl HashAlgorithm {
+    /// Every supported algorithm, in the order they should be presented to the user.
+    pub const ALL: [Self; 18] = [
+        Self::Md5,
+        Self::Sha1,
+        Self::Sha224,
+        Self::Sha256,
+        Self::Sha384,
+        Self::Sha512,
+        Self::Sha512_256,
+        Self::Sha3_256,
+        Self::Sha3_512,
+        Self::Blake2b,
+        Self::Blake2s,
+        Self::Blake3,
+        Self::Crc32,
+        Self::Crc32c,
+        Self::Adler32,
+        Self::Xxh64,
+        Self::Xxh3_64,
+        Self::Xxh3_128,
+    ];
+
+    /// The human-readable name of the algorithm, e.g. `SHA-256`.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Md5 => "MD5",
+            Self::Sha1 => "SHA-1",
+            Self::Sha224 => "SHA-224",
+            Self::Sha256 => "SHA-256",
+            Self::Sha384 => "SHA-384",
+            Self::Sha512 => "SHA-512",
+            Self::Sha512_256 => "SHA-512/256",
+            Self::Sha3_256 => "SHA3-256",
+            Self::Sha3_512 => "SHA3-512",
+            Self::Blake2b => "BLAKE2b",
+            Self::Blake2s => "BLAKE2s",
+            Self::Blake3 => "BLAKE3",
+            Self::Crc32 => "CRC-32",
+            Self::Crc32c => "CRC-32C",
+            Self::Adler32 => "Adler-32",
+            Self::Xxh64 => "XXH64",
+            Self::Xxh3_64 => "XXH3-64",
+            Self::Xxh3_128 => "XXH3-128",
+        }
+    }
+
+    /// A short lowercase identifier for the algorithm, e.g. `sha256`, as used in labelled digests.
+    #[must_use]
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::Md5 => "md5",
+            Self::Sha1 => "sha1",
+            Self::Sha224 => "sha224",
+            Self::Sha256 => "sha256",
+            Self::Sha384 => "sha384",
+            Self::Sha512 => "sha512",
+            Self::Sha512_256 => "sha512-256",
+            Self::Sha3_256 => "sha3-256",
+            Self::Sha3_512 => "sha3-512",
+            Self::Blake2b => "blake2b",
+            Self::Blake2s => "blake2s",
+            Self::Blake3 => "blake3",
+            Self::Crc32 => "crc32",
+            Self::Crc32c => "crc32c",
+            Self::Adler32 => "adler32",
+            Self::Xxh64 => "xxh64",
+            Self::Xxh3_64 => "xxh3",
+            Self::Xxh3_128 => "xxh128",
+        }
+    }
+
+    /// The algorithm name used in BSD-style tagged lines, e.g. `SHA256`, matching
+    /// `sha256sum --tag`, `b2sum --tag` and `xxhsum --tag`.
+    #[must_use]
+    pub fn bsd_tag(self) -> &'static str {
+        match self {
+            Self::Md5 => "MD5",
+            Self::Sha1 => "SHA1",
+            Self::Sha224 => "SHA224",
+            Self::Sha256 => "SHA256",
+            Self::Sha384 => "SHA384",
+            Self::Sha512 => "SHA512",
+            Self::Sha512_256 => "SHA512-256",
+            Self::Sha3_256 => "SHA3-256",
+            Self::Sha3_512 => "SHA3-512",
+            Self::Blake2b => "BLAKE2b",
+            Self::Blake2s => "BLAKE2s",
+            Self::Blake3 => "BLAKE3",
+            Self::Crc32 => "CRC32",
+            Self::Crc32c => "CRC32C",
+            Self::Adler32 => "ADLER32",
+            // As written by `xxhsum --tag`.
+            Self::Xxh64 => "XXH64",
+            Self::Xxh3_64 => "XXH3",
+            Self::Xxh3_128 => "XXH128",
+        }
+    }
+
+    /// Whether the algorithm resists deliberate tampering.
+    ///
+    /// Checksums such as CRC-32 and xxHash only detect accidental corruption: anyone can make
+    /// a file with whatever checksum they like, so a match says nothing about where it came from.
+    /// MD5 and SHA-1 count as cryptographic here even though collisions can be made for them.
+    #[must_use]
+    pub fn is_cryptographic(self) -> bool {
+        !matches!(
+            self,
+            Self::Crc32
+                | Self::Crc32c
+                | Self::Adler32
+                | Self::Xxh64
+                | Self::Xxh3_64
+                | Self::Xxh3_128
+        )
+    }
+
+    /// The length of the digest produced by the algorithm, in bytes.
+    #[must_use]
+    pub fn output_len(self) -> usize {
+        match self {
+            Self::Crc32 | Self::Crc32c | Self::Adler32 => 4,
+            Self::Xxh64 | Self::Xxh3_64 => 8,
+            Self::Md5 | Self::Xxh3_128 => 16,
+            Self::Sha1 => 20,
+            Self::Sha224 => 28,
+            Self::Sha256 | Self::Sha512_256 | Self::Sha3_256 | Self::Blake2s | Self::Blake3 => 32,
+            Self::Sha384 => 48,
+            Self::Sha512 | Self::Sha3_512 | Self::Blake2b => 64,
+        }
+    }
+
+    fn hasher(self) -> Hasher {
+        match self {
+            Self::Md5 => Hasher::Md5(md5::Md5::new()),
+            Self::Sha1 => Hasher::Sha1(sha1::Sha1::new()),
+            Self::Sha224 => Hasher::Sha224(sha2::Sha224::new()),
+            Self::Sha256 => Hasher::Sha256(sha2::Sha256::new()),
+            Self::Sha384 => Hasher::Sha384(sha2::Sha384::new()),
+            Self::Sha512 => Hasher::Sha512(sha2::Sha512::new()),
+            Self::Sha512_256 => Hasher::Sha512_256(sha2::Sha512_256::new()),
+            Self::Sha3_256 => Hasher::Sha3_256(sha3::Sha3_256::new()),
+            Self::Sha3_512 => Hasher::Sha3_512(sha3::Sha3_512::new()),
+            Self::Blake2b => Hasher::Blake2b(blake2::Blake2b512::new()),
+            Self::Blake2s => Hasher::Blake2s(blake2::Blake2s256::new()),
+            Self::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+            Self::Crc32 => Hasher::Crc32(crc32fast::Hasher::new()),
+            Self::Crc32c => Hasher::Crc32c(0),
+            Self::Adler32 => Hasher::Adler32(adler2::Adler32::new()),
+            Self::Xxh64 => Hasher::Xxh64(xxhash_rust::xxh64::Xxh64::new(0)),
+            Self::Xxh3_64 => Hasher::Xxh3_64(Box::new(xxhash_rust::xxh3::Xxh3::new())),
+            Self::Xxh3_128 => Hasher::Xxh3_128(Box::new(xxhash_rust::xxh3::Xxh3::new())),
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = UnknownAlgorithm;
+
+    /// Parses an algorithm name or [`HashAlgorithm::id`] case-insensitively, ignoring `-`, `_`
+    /// and `/` separators, so `SHA-256`, `sha256` and `Sha_256` are all accepted.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn normalize(s: &str) -> String {
+            s.chars()
+                .filter(|c| !matches!(c, '-' | '_' | '/'))
+                .map(|c| c.to_ascii_lowercase())
+                .collect()
+        }
+        let normalized = normalize(s);
+        Self::ALL
+            .into_iter()
+            .find(|algorithm| {
+                normalize(algorithm.name()) == normalized || normalize(algorithm.id()) == normalized
+            })
+            .ok_or_else(|| UnknownAlgorithm(s.to_owned()))
+    }
+}
+
+/// The error returned when parsing an unrecognised [`HashAlgorithm`] name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownAlgorithm(pub String);
+
+impl fmt::Display for UnknownAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown hash algorithm: {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownAlgorithm {}
+
+/// The running state of one of the [`HashAlgorithm`]s.
+enum Hasher {
+    Md5(md5::Md5),
+    Sha1(sha1::Sha1),
+    Sha224(sha2::Sha224),
+    Sha256(sha2::Sha256),
+    Sha384(sha2::Sha384),
+    Sha512(sha2::Sha512),
+    Sha512_256(sha2::Sha512_256),
+    Sha3_256(sha3::Sha3_256),
+    Sha3_512(sha3::Sha3_512),
+    Blake2b(blake2::Blake2b512),
+    Blake2s(blake2::Blake2s256),
+    Blake3(Box<blake3::Hasher>),
+    Crc32(crc32fast::Hasher),
+    Crc32c(u32),
+    Adler32(adler2::Adler32),
+    Xxh64(xxhash_rust::xxh64::Xxh64),
+    Xxh3_64(Box<xxhash_rust::xxh3::Xxh3>),
+    Xxh3_128(Box<xxhash_rust::xxh3::Xxh3>),
+}
+
+impl Hasher {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Md5(hasher) => hasher.update(data),
+            Self::Sha1(hasher) => hasher.update(data),
+            Self::Sha224(hasher) => hasher.update(data),
+            Self::Sha256(hasher) => hasher.update(data),
+            Self::Sha384(hasher) => hasher.update(data),
+            Self::Sha512(hasher) => hasher.update(data),
+            Self::Sha512_256(hasher) => hasher.update(data),
+            Self::Sha3_256(hasher) => hasher.update(data),
+            Self::Sha3_512(hasher) => hasher.update(data),
+            Self::Blake2b(hasher) => hasher.update(data),
+            Self::Blake2s(hasher) => hasher.update(data),
+            Self::Blake3(hasher) => {
+                hasher.update(data);
+            }
+            Self::Crc32(hasher) => hasher.update(data),
+            Self::Crc32c(crc) => *crc = crc32c::crc32c_append(*crc, data),
+            Self::Adler32(hasher) => hasher.write_slice(data),
+            Self::Xxh64(hasher) => hasher.update(data),
+            Self::Xxh3_64(hasher) | Self::Xxh3_128(hasher) => hasher.update(data),
+        }
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            Self::Md5(hasher) => hasher.finalize().to_vec(),
+            Self::Sha1(hasher) => hasher.finalize().to_vec(),
+            Self::Sha224(hasher) => hasher.finalize().to_vec(),
+            Self::Sha256(hasher) => hasher.finalize().to_vec(),
+            Self::Sha384(hasher) => hasher.finalize().to_vec(),
+            Self::Sha512(hasher) => hasher.finalize().to_vec(),
+            Self::Sha512_256(hasher) => hasher.finalize().to_vec(),
+            Self::Sha3_256(hasher) => hasher.finalize().to_vec(),
+            Self::Sha3_512(hasher) => hasher.finalize().to_vec(),
+            Self::Blake2b(hasher) => hasher.finalize().to_vec(),
+            Self::Blake2s(hasher) => hasher.finalize().to_vec(),
+            Self::Blake3(hasher) => hasher.finalize().as_bytes().to_vec(),
+            // Checksums are conventionally written as big-endian numbers.
+            Self::Crc32(hasher) => hasher.finalize().to_be_bytes().to_vec(),
+            Self::Crc32c(crc) => crc.to_be_bytes().to_vec(),
+            Self::Adler32(hasher) => hasher.checksum().to_be_bytes().to_vec(),
+            Self::Xxh64(hasher) => hasher.digest().to_be_bytes().to_vec(),
+            Self::Xxh3_64(hasher) => hasher.digest().to_be_bytes().to_vec(),
+            Self::Xxh3_128(hasher) => hasher.digest128().to_be_bytes().to_vec(),
+        }
+    }
+}
+
+/// How a file's contents are read while hashing.
+///
+/// The default suits most files. For large files on fast disks, bigger reads or memory mapping
+/// spend less time in system calls and copying; `cargo bench --bench read_strategies` measures
+/// the difference on your machine.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize,
+)]
+pub enum ReadStrategy {
+    /// Read through a 64 KiB buffer.
+    #[default]
+    Buffered,
+
+    /// Read through a 4 MiB buffer, a whole number of pages, making far fewer system calls.
+    LargeBuffer,
+
+    /// Map the file into memory and hash it in place, without copying it into a buffer.
+    ///
+    /// Only available with the `mmap` feature on native targets. Falls back to
+    /// [`ReadStrategy::LargeBuffer`] when the feature is disabled or the file can't be mapped,
+    /// as with pipes and some network file systems.
+    Mmap,
+}
+
+impl ReadStrategy {
+    pub const ALL: [Self; 3] = [Self::Buffered, Self::LargeBuffer, Self::Mmap];
+
+    /// A short lowercase identifier for the strategy, e.g. `mmap`.
+    #[must_use]
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::Buffered => "buffered",
+            Self::LargeBuffer => "large",
+            Self::Mmap => "mmap",
+        }
+    }
+
+    /// The size of the buffer each read is made into.
+    fn buffer_size(self) -> usize {
+        match self {
+            Self::Buffered => 64 * 1024,
+            Self::LargeBuffer | Self::Mmap => 4 * 1024 * 1024,
+        }
+    }
+}
+
+impl fmt::Display for ReadStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+impl FromStr for ReadStrategy {
+    type Err = UnknownReadStrategy;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|strategy| strategy.id().eq_ignore_ascii_case(s))
+            .ok_or_else(|| UnknownReadStrategy(s.to_owned()))
+    }
+}
+
+/// The error returned when parsing an unrecognised [`ReadStrategy`] name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownReadStrategy(pub String);
+
+impl fmt::Display for UnknownReadStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown read strategy {:?} (expected buffered, large or mmap)",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for UnknownReadStrategy {}
+
+/// The size files are hashed by [`HashOptions::parallel_blake3`] above, unless told otherwise.
+///
+/// Below this, handing the work to other threads costs about as much as it saves.
+pub const PARALLEL_BLAKE3_MIN_LEN: u64 = 64 * 1024 * 1024;
+
+/// How much data is handed to BLAKE3 at a time when it hashes on every core. Each buffer is
+/// split across the threads, so it needs to be far bigger than a single-threaded read.
+const PARALLEL_BUFFER_SIZE: usize = 16 * 1024 * 1024;
+
+/// How many bytes of a memory-mapped file are hashed between progress reports.
+#[cfg(all(feature = "mmap", not(target_arch = "wasm32")))]
+const MMAP_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Feeds every buffer read into several [`Hasher`]s at once,
+/// so a file only has to be read once no matter how many digests are wanted.
+struct MultiHasher {
+    hashers: Vec<(HashAlgorithm, Hasher)>,
+
+    /// Whether BLAKE3 spreads each buffer across every core. The other algorithms can't.
+    parallel: bool,
+}
+
+impl MultiHasher {
+    fn new(algorithms: &[HashAlgorithm], options: &HashOptions<'_>) -> Self {
+        let mut algorithms = algorithms.to_vec();
+        algorithms.sort_unstable();
+        algorithms.dedup();
+        Self {
+            // There are no threads on the web.
+            parallel: cfg!(not(target_arch = "wasm32"))
+                && algorithms.contains(&HashAlgorithm::Blake3)
+                && options
+                    .parallel_blake3
+                    .is_some_and(|min_len| options.total_bytes.unwrap_or(0) >= min_len),
+            hashers: algorithms
+                .into_iter()
+                .map(|algorithm| (algorithm, algorithm.hasher()))
+                .collect(),
+        }
+    }
+
+    /// How much to read at a time.
+    fn buffer_size(&self, options: &HashOptions<'_>) -> usize {
+        let size = options.read_strategy.buffer_size();
+        if self.parallel {
+            size.max(PARALLEL_BUFFER_SIZE)
+        } else {
+            size
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for (_, hasher) in &mut self.hashers {
+            match hasher {
+                #[cfg(not(target_arch = "wasm32"))]
+                Hasher::Blake3(hasher) if self.parallel => {
+                    hasher.update_rayon(data);
+                }
+                hasher => hasher.update(data),
+            }
+        }
+    }
+
+    /// Feeds everything `reader` yields into the hashers, returning the number of bytes read.
+    ///
+    /// Progress is reported and cancellation checked once per buffer.
+    fn consume(
+        &mut self,
+        reader: &mut impl Read,
+        path: Option<&Path>,
+        options: &mut HashOptions<'_>,
+    ) -> Result<u64, HashError> {
+        let start = Instant::now();
+        let mut buffer = vec![0; self.buffer_size(options)];
+        let mut offset = 0;
+        loop {
+            if options.is_cancelled() {
+                return Err(HashError::Cancelled {
+                    path: path.map(Path::to_owned),
+                    offset,
+                });
+            }
+            match reader.read(&mut buffer) {
+                Ok(0) => return Ok(offset),
+                Ok(n) => {
+                    self.update(&buffer[..n]);
+                    offset += n as u64;
+                    if let Some(on_progress) = &mut options.on_progress {
+                        on_progress(&Progress {
+                            bytes_processed: offset,
+                            total_bytes: options.total_bytes,
+                            elapsed: start.elapsed(),
+                        });
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(source) => {
+                    return Err(HashError::Read {
+                        path: path.map(Path::to_owned),
+                        offset,
+                        source,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Feeds `data`, such as a memory-mapped file, into the hashers in chunks, reporting
+    /// progress and checking cancellation between them.
+    #[cfg(all(feature = "mmap", not(target_arch = "wasm32")))]
+    fn consume_bytes(
+        &mut self,
+        data: &[u8],
+        path: Option<&Path>,
+        options: &mut HashOptions<'_>,
+    ) -> Result<u64, HashError> {
+        let start = Instant::now();
+        let mut offset = 0;
+        for chunk in data.chunks(self.buffer_size(options).max(MMAP_CHUNK_SIZE)) {
+            if options.is_cancelled() {
+                return Err(HashError::Cancelled {
+                    path: path.map(Path::to_owned),
+                    offset,
+                });
+            }
+            self.update(chunk);
+            offset += chunk.len() as u64;
+            if let Some(on_progress) = &mut options.on_progress {
+                on_progress(&Progress {
+                    bytes_processed: offset,
+                    total_bytes: options.total_bytes,
+                    elapsed: start.elapsed(),
+                });
+            }
+        }
+        Ok(offset)
+    }
+
+    fn finalize(self) -> BTreeMap<HashAlgorithm, Digest> {
+        self.hashers
+            .into_iter()
+            .map(|(algorithm, hasher)| {
+                let digest = Digest::new(algorithm, hasher.finalize())
+                    .expect("every hasher produces its algorithm's output length");
+                (algorithm, digest)
+            })
+            .collect()
+    }
+}
+
+/// An error that occurred while hashing a file or stream.
+#[derive(Debug)]
+pub enum HashError {
+    /// The file does not exist.
+    NotFound { path: PathBuf },
+
+    /// The file exists but we are not allowed to open it.
+    PermissionDenied { path: PathBuf },
+
+    /// The path refers to a directory rather than a file.
+    IsADirectory { path: PathBuf },
+
+    /// The file could not be opened for any other reason.
+    Open { path: PathBuf, source: io::Error },
+
+    /// Reading the file failed after `offset` bytes had been hashed successfully.
+    ///
+    /// `path` is `None` when hashing a stream rather than a file.
+    Read {
+        path: Option<PathBuf>,
+        offset: u64,
+        source: io::Error,
+    },
+
+    /// Hashing was stopped through a [`CancellationToken`] after `offset` bytes.
+    Cancelled { path: Option<PathBuf>, offset: u64 },
+}
+
+impl HashError {
+    /// Classifies an error returned when opening the file at `path`.
+    pub(crate) fn open(path: &Path, source: io::Error) -> Self {
+        let path = path.to_owned();
+        match source.kind() {
+            io::ErrorKind::NotFound => Self::NotFound { path },
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied { path },
+            io::ErrorKind::IsADirectory => Self::IsADirectory { path },
+            _ => Self::Open { path, source },
+        }
+    }
+
+    /// The path of the file that could not be hashed, if the data came from a file.
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::NotFound { path }
+            | Self::PermissionDenied { path }
+            | Self::IsADirectory { path }
+            | Self::Open { path, .. } => Some(path),
+            Self::Read { path, .. } | Self::Cancelled { path, .. } => path.as_deref(),
+        }
+    }
+}
+
+impl fmt::Display for HashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound { path } => write!(f, "{}: file not found", path.display()),
+            Self::PermissionDenied { path } => write!(f, "{}: permission denied", path.display()),
+            Self::IsADirectory { path } => write!(f, "{}: is a directory", path.display()),
+            Self::Open { path, source } => {
+                write!(f, "{}: failed to open: {source}", path.display())
+            }
+            Self::Read {
+                path: Some(path),
+                offset,
+                source,
+            } => write!(
+                f,
+                "{}: read failed after {offset} bytes: {source}",
+                path.display()
+            ),
+            Self::Read {
+                path: None,
+                offset,
+                source,
+            } => write!(f, "read failed after {offset} bytes: {source}"),
+            Self::Cancelled {
+                path: Some(path),
+                offset,
+            } => write!(f, "{}: cancelled after {offset} bytes", path.display()),
+            Self::Cancelled { path: None, offset } => write!(f, "cancelled after {offset} bytes"),
+        }
+    }
+}
+
+impl std::error::Error for HashError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Open { source, .. } | Self::Read { source, .. } => Some(source),
+            Self::NotFound { .. }
+            | Self::PermissionDenied { .. }
+            | Self::IsADirectory { .. }
+            | Self::Cancelled { .. } => None,
+        }
+    }
+}
+
+/// A snapshot of how far along a running hash is, passed to [`HashOptions::on_progress`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    /// The number of bytes hashed so far.
+    pub bytes_processed: u64,
+
+    /// The total number of bytes that will be hashed, if known up front.
+    pub total_bytes: Option<u64>,
+
+    /// The time since hashing started.
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// The fraction of the input hashed so far, between `0.0` and `1.0`, if the total is known.
+    #[must_use]
+    pub fn fraction(&self) -> Option<f32> {
+        let total = self.total_bytes?;
+        if total == 0 {
+            return Some(1.0);
+        }
+        Some((self.bytes_processed as f64 / total as f64).min(1.0) as f32)
+    }
+
+    /// The average throughput so far, in bytes per second.
+    #[must_use]
+    pub fn throughput(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.bytes_processed as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+}
+
+/// A handle that can be used to stop a running hash from another thread.
+///
+/// Clones share the same state, so cancelling any clone cancels them all.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests that every hash using this token stops at the next buffer boundary.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+type ProgressCallback<'a> = Box<dyn FnMut(&Progress) + 'a>;
+
+/// Optional behaviour for [`hash_file_with`] and [`hash_reader_with`].
+///
+/// # Examples
+///
+/// ```rust
+/// use hash_checker::{CancellationToken, HashAlgorithm, HashOptions, hash_file_with};
+/// let token = CancellationToken::new();
+/// let options = HashOptions::new()
+///     .on_progress(|progress| println!("{} bytes hashed", progress.bytes_processed))
+///     .cancellation(token.clone());
+/// let hashes = hash_file_with("examples/valid.txt", &[HashAlgorithm::Sha256], options);
+/// ```
+#[derive(Default)]
+pub struct HashOptions<'a> {
+    on_progress: Option<ProgressCallback<'a>>,
+    cancellation: Option<CancellationToken>,
+    total_bytes: Option<u64>,
+    read_strategy: ReadStrategy,
+    parallel_blake3: Option<u64>,
+}
+
+impl<'a> HashOptions<'a> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls `on_progress` after every buffer has been hashed.
+    #[must_use]
+    pub fn on_progress(mut self, on_progress: impl FnMut(&Progress) + 'a) -> Self {
+        self.on_progress = Some(Box::new(on_progress));
+        self
+    }
+
+    /// Stops hashing with [`HashError::Cancelled`] once `token` is cancelled.
+    #[must_use]
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Sets the total length reported in [`Progress::total_bytes`].
+    ///
+    /// This is filled in automatically when hashing a file.
+    #[must_use]
+    pub fn total_bytes(mut self, total_bytes: u64) -> Self {
+        self.total_bytes = Some(total_bytes);
+        self
+    }
+
+    /// Sets how files are read. Readers passed to [`hash_reader_with`] are always read through
+    /// a buffer of the strategy's size.
+    #[must_use]
+    pub fn read_strategy(mut self, read_strategy: ReadStrategy) -> Self {
+        self.read_strategy = read_strategy;
+        self
+    }
+
+    /// Hashes inputs of at least `min_len` bytes with BLAKE3 on every core, using its tree
+    /// structure to split the work. [`PARALLEL_BLAKE3_MIN_LEN`] is a reasonable threshold.
+    ///
+    /// Streams of unknown length count as empty. Other algorithms are unaffected, and so is
+    /// the web build, which has no threads.
+    #[must_use]
+    pub fn parallel_blake3(mut self, min_len: u64) -> Self {
+        self.parallel_blake3 = Some(min_len);
+        self
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+}
+
+/// Opens the file at `path` for hashing, returning it along with its length.
+///
+/// Directories are rejected up front since on some platforms they can be opened but not read.
+fn open_file(path: &Path) -> Result<(File, u64), HashError> {
+    let file = File::open(path).map_err(|err| HashError::open(path, err))?;
+    let metadata = file.metadata().map_err(|err| HashError::open(path, err))?;
+    if metadata.is_dir() {
+        return Err(HashError::IsADirectory {
+            path: path.to_owned(),
+        });
+    }
+    Ok((file, metadata.len()))
+}
+
+/// Feeds `file` into `hasher` using the read strategy chosen in `options`.
+fn consume_file(
+    hasher: &mut MultiHasher,
+    file: &mut File,
+    path: &Path,
+    options: &mut HashOptions<'_>,
+) -> Result<u64, HashError> {
+    // Empty files can't be mapped, and there's nothing to gain from it anyway.
+    #[cfg(all(feature = "mmap", not(target_arch = "wasm32")))]
+    if options.read_strategy == ReadStrategy::Mmap && options.total_bytes != Some(0) {
+        match crate::mmap::map(file) {
+            Ok(map) => return hasher.consume_bytes(&map, Some(path), options),
+            Err(err) => debug!("Reading {} instead of mapping it: {err}", path.display()),
+        }
+    }
+    hasher.consume(file, Some(path), options)
+}
+
+/// Reads the whole file at `path` into memory, reporting failures the same way hashing does.
+#[expect(
+    clippy::verbose_file_reads,
+    reason = "open_file tells missing files from directories"
+)]
+pub(crate) fn read_file(path: &Path) -> Result<Vec<u8>, HashError> {
+    let (mut file, len) = open_file(path)?;
+    let mut contents = Vec::with_capacity(usize::try_from(len).unwrap_or_default());
+    file.read_to_end(&mut contents)
+        .map_err(|source| HashError::Read {
+            path: Some(path.to_owned()),
+            offset: contents.len() as u64,
+            source,
+        })?;
+    Ok(contents)
+}
+
+/// Computes the hash of everything read from `reader` using every one of the given algorithms,
+/// reading the stream only once.
+///
+/// # Arguments
+///
+/// * `reader` - The stream to be hashed, read until end of file. It is read in large chunks,
+///   so there is no need to wrap it in a [`std::io::BufReader`].
+/// * `algorithms` - The [`HashAlgorithm`]s to hash the stream with. Duplicates are ignored.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a map from each algorithm to its [`Digest`] on success,
+/// or a [`HashError`] if reading from the stream fails.
+///
+/// # Errors
+///
+/// This function will return [`HashError::Read`] if any I/O error other than
+/// [`io::ErrorKind::Interrupted`] occurs while reading.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use hash_checker::{HashAlgorithm, hash_reader_multi};
+/// let algorithms = [HashAlgorithm::Sha256, HashAlgorithm::Blake3];
+/// let hashes = hash_reader_multi(std::io::stdin().lock(), &algorithms);
+/// ```
+pub fn hash_reader_multi<R: Read>(
+    reader: R,
+    algorithms: &[HashAlgorithm],
+) -> Result<BTreeMap<HashAlgorithm, Digest>, HashError> {
+    hash_reader_with(reader, algorithms, HashOptions::default())
+}
+
+/// Like [`hash_reader_multi`], but reporting progress and honouring cancellation
+/// as configured in `options`.
+///
+/// # Errors
+///
+/// This function will return [`HashError::Read`] if any I/O error other than
+/// [`io::ErrorKind::Interrupted`] occurs while reading,
+/// or [`HashError::Cancelled`] if the cancellation token in `options` is cancelled.
+pub fn hash_reader_with<R: Read>(
+    mut reader: R,
+    algorithms: &[HashAlgorithm],
+    mut options: HashOptions<'_>,
+) -> Result<BTreeMap<HashAlgorithm, Digest>, HashError> {
+    let mut hasher = MultiHasher::new(algorithms, &options);
+    let n = hasher.consume(&mut reader, None, &mut options)?;
+    debug!("Read {n} bytes from stream");
+    Ok(hasher.finalize())
+}
+
+/// Computes the hash of everything read from `reader` using the given algorithm.
+///
+/// This is a shorthand for [`hash_reader_multi`] with a single algorithm.
+///
+/// # Errors
+///
+/// This function will return [`HashError::Read`] if any I/O error other than
+/// [`io::ErrorKind::Interrupted`] occurs while reading.
+///
+/// # Examples
+///
+/// ```rust
+/// use hash_checker::{HashAlgorithm, hash_reader};
+/// let data: &[u8] = b"123456789\n";
+/// let hash = hash_reader(data, HashAlgorithm::Sha256).expect("reading a slice cannot fail");
+/// assert_eq!(
+///     hash.to_string(),
+///     "6d78392a5886177fe5b86e585a0b695a2bcd01a05504b3c4e38bc8eeb21e8326"
+/// );
+/// ```
+pub fn hash_reader<R: Read>(reader: R, algorithm: HashAlgorithm) -> Result<Digest, HashError> {
+    let mut hashes = hash_reader_multi(reader, &[algorithm])?;
+    Ok(hashes
+        .remove(&algorithm)
+        .expect("every requested algorithm produces a hash"))
+}
+
+/// Computes the hash of an in-memory byte slice using the given algorithm.
+///
+/// # Examples
+///
+/// ```rust
+/// use hash_checker::{HashAlgorithm, hash_bytes};
+/// let hash = hash_bytes(b"", HashAlgorithm::Blake3);
+/// assert_eq!(
+///     hash.to_string(),
+///     "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+/// );
+/// ```
+#[must_use]
+pub fn hash_bytes(data: &[u8], algorithm: HashAlgorithm) -> Digest {
+    let mut hasher = algorithm.hasher();
+    hasher.update(data);
+    Digest::new(algorithm, hasher.finalize())
+        .expect("every hasher produces its algorithm's output length")
+}
+
+/// Computes the hash of the contents of a file at the given path using every one of the given algorithms,
+/// reading the file only once.
 ///
 /// # Arguments
 ///
-/// * `path` - A string slice that holds the path to the file to be hashed.
+/// * `path` - The path of the file to be hashed. It does not need to be valid UTF-8.
+/// * `algorithms` - The [`HashAlgorithm`]s to hash the file with. Duplicates are ignored.
 ///
 /// # Returns
 ///
-/// Returns a `Result` containing the Base64-encoded SHA-256 hash as a `String` on success,
-/// or a boxed error (`Box<dyn std::error::Error>`) if an error occurs while reading the file or computing the hash.
+/// Returns a `Result` containing a map from each algorithm to its [`Digest`] on success,
+/// or a [`HashError`] if an error occurs while reading the file.
 ///
 /// # Errors
 ///
-/// This function will return an error if the file cannot be opened, read, or if any I/O error occurs during hashing.
+/// This function will return an error if the file does not exist, cannot be opened, is a directory,
+/// or if any I/O error occurs while reading it.
+///
+/// # Examples
+///
+/// ```rust
+/// use hash_checker::{HashAlgorithm, hash_file_multi};
+/// let algorithms = [HashAlgorithm::Sha256, HashAlgorithm::Sha512, HashAlgorithm::Blake3];
+/// if let Ok(hashes) = hash_file_multi("examples/valid.txt", &algorithms) {
+///     for (algorithm, hash) in hashes {
+///         println!("{algorithm} hash: {hash}");
+///     }
+/// }
+/// ```
+pub fn hash_file_multi(
+    path: impl AsRef<Path>,
+    algorithms: &[HashAlgorithm],
+) -> Result<BTreeMap<HashAlgorithm, Digest>, HashError> {
+    hash_file_with(path, algorithms, HashOptions::default())
+}
+
+/// Like [`hash_file_multi`], but reporting progress and honouring cancellation
+/// as configured in `options`.
+///
+/// # Errors
+///
+/// This function will return an error if the file does not exist, cannot be opened, is a directory,
+/// if any I/O error occurs while reading it,
+/// or with [`HashError::Cancelled`] if the cancellation token in `options` is cancelled.
+pub fn hash_file_with(
+    path: impl AsRef<Path>,
+    algorithms: &[HashAlgorithm],
+    mut options: HashOptions<'_>,
+) -> Result<BTreeMap<HashAlgorithm, Digest>, HashError> {
+    let path = path.as_ref();
+    let (mut file, len) = open_file(path)?;
+    options.total_bytes.get_or_insert(len);
+
+    let mut hasher = MultiHasher::new(algorithms, &options);
+    let n = consume_file(&mut hasher, &mut file, path, &mut options)?;
+    debug!("Read {n} bytes from {}", path.display());
+
+    let hashes = hasher
+        .finalize()
+        .into_iter()
+        .inspect(|(algorithm, digest)| {
+            debug!("Computed {algorithm} hash for {}: {digest}", path.display());
+        })
+        .collect();
+
+    Ok(hashes)
+}
+
+/// Like [`hash_file_with`] with a single algorithm, but hashing `header(len)` before the
+/// contents, for formats such as git objects that start with the file's length.
+///
+/// # Errors
+///
+/// As [`hash_file_with`], and with [`HashError::Read`] if the file changes length while it is
+/// being hashed, since the header would no longer match.
+pub(crate) fn hash_file_with_header(
+    path: &Path,
+    algorithm: HashAlgorithm,
+    header: impl FnOnce(u64) -> Vec<u8>,
+    mut options: HashOptions<'_>,
+) -> Result<Digest, HashError> {
+    let (mut file, len) = open_file(path)?;
+    options.total_bytes.get_or_insert(len);
+
+    let mut hasher = MultiHasher::new(&[algorithm], &options);
+    hasher.update(&header(len));
+    let n = consume_file(&mut hasher, &mut file, path, &mut options)?;
+    if n != len {
+        return Err(HashError::Read {
+            path: Some(path.to_owned()),
+            offset: n,
+            source: io::Error::other(format!("file changed from {len} bytes while being read")),
+        });
+    }
+    debug!("Read {n} bytes from {}", path.display());
+
+    Ok(hasher
+        .finalize()
+        .remove(&algorithm)
+        .expect("every requested algorithm produces a hash"))
+}
+
+/// Computes the hash of the contents of a file at the given path using the given algorithm.
+///
+/// This is a shorthand for [`hash_file_multi`] with a single algorithm.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file to be hashed. It does not need to be valid UTF-8.
+/// * `algorithm` - The [`HashAlgorithm`] to hash the file with.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the [`Digest`] of the file on success,
+/// or a [`HashError`] if an error occurs while reading the file.
+///
+/// # Errors
+///
+/// This function will return an error if the file does not exist, cannot be opened, is a directory,
+/// or if any I/O error occurs while reading it.
+///
+/// # Examples
+///
+/// ```rust
+/// use hash_checker::{HashAlgorithm, hash_file};
+/// let result = hash_file("examples/valid.txt", HashAlgorithm::Blake3);
+/// if let Ok(hash) = result {
+///     println!("BLAKE3 hash: {}", hash);
+/// }
+/// ```
+pub fn hash_file(path: impl AsRef<Path>, algorithm: HashAlgorithm) -> Result<Digest, HashError> {
+    let mut hashes = hash_file_multi(path, &[algorithm])?;
+    Ok(hashes
+        .remove(&algorithm)
+        .expect("every requested algorithm produces a hash"))
+}
+
+/// Computes the SHA-256 hash of the contents of a file at the given path.
+///
+/// This is a shorthand for [`hash_file`] with [`HashAlgorithm::Sha256`].
+///
+/// # Arguments
+///
+/// * `path` - The path of the file to be hashed. It does not need to be valid UTF-8.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the SHA-256 [`Digest`] of the file on success,
+/// or a [`HashError`] if an error occurs while reading the file.
+///
+/// # Errors
+///
+/// This function will return an error if the file does not exist, cannot be opened, is a directory,
+/// or if any I/O error occurs while reading it.
 ///
 /// # Examples
 ///
@@ -31,19 +1087,8 @@ use std::io::BufReader;
 ///     println!("SHA-256 hash: {}", hash);
 /// }
 /// ```
-pub fn hash_sha256(path: &str) -> Result<String, Box<dyn std::error::Error>> {
-    let file = File::open(path)?;
-    let mut reader = BufReader::new(file);
-
-    let mut hasher = Sha256::new();
-    let n = io::copy(&mut reader, &mut hasher)?;
-    debug!("Read {n} bytes from {path}");
-
-    let hash = hasher.finalize();
-    let base64 = base16ct::lower::encode_string(&hash);
-    debug!("Computed SHA-256 hash for {path}: {base64}");
-
-    Ok(base64)
+pub fn hash_sha256(path: impl AsRef<Path>) -> Result<Digest, HashError> {
+    hash_file(path, HashAlgorithm::Sha256)
 }
 
 #[cfg(test)]
@@ -52,9 +1097,12 @@ mod tests {
 
     #[test]
     fn test_valid_file() {
-        let hash = hash_sha256("examples/valid.txt")
-            .expect("Expected valid.txt to hash successfully.");
-        assert_eq!(hash, "6d78392a5886177fe5b86e585a0b695a2bcd01a05504b3c4e38bc8eeb21e8326");
+        let hash =
+            hash_sha256("examples/valid.txt").expect("Expected valid.txt to hash successfully.");
+        assert_eq!(
+            hash.to_string(),
+            "6d78392a5886177fe5b86e585a0b695a2bcd01a05504b3c4e38bc8eeb21e8326"
+        );
     }
 
     #[test]
@@ -62,4 +1110,221 @@ mod tests {
         let result = hash_sha256("examples/invalid.txt");
         assert!(result.is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_error_kinds() {
+        let err = hash_sha256("examples/invalid.txt").expect_err("invalid.txt does not exist");
+        assert!(matches!(err, HashError::NotFound { .. }), "got {err:?}");
+        assert_eq!(err.path(), Some(Path::new("examples/invalid.txt")));
+
+        let err = hash_sha256("examples").expect_err("examples is a directory");
+        assert!(matches!(err, HashError::IsADirectory { .. }), "got {err:?}");
+    }
+
+    #[test]
+    fn test_all_algorithms() {
+        let expected = [
+            (HashAlgorithm::Md5, "b2cfa4183267af678ea06c7407d4d6d8"),
+            (
+                HashAlgorithm::Sha1,
+                "179c94cf45c6e383baf52621687305204cef16f9",
+            ),
+            (
+                HashAlgorithm::Sha224,
+                "cceb9e687ff6ada101d2e1542c08c244cf5f29c1d78752fa0f231439",
+            ),
+            (
+                HashAlgorithm::Sha384,
+                "48e42be9e9f30cf89f419e9db70aeff8b9a826ccf660836a33a9313e17023aab03153ee7bfa90f3859a18e89c03013aa",
+            ),
+            (
+                HashAlgorithm::Sha512,
+                "ff3245abe317049ed1b8aa7aa2f4c4dcb8bf86f083ed67eb26b43e2fbe3ba8fdf759f9e2f46fcf2a06c2dfeddf0cedcd41a68034cd618b880785b34f759d1a69",
+            ),
+            (
+                HashAlgorithm::Sha512_256,
+                "070a386431cdcbd1cee697e8517a31b1ece40df99521fa220b4f2de7fd133e26",
+            ),
+            (
+                HashAlgorithm::Sha3_256,
+                "044a2cfbc4e8143f285228036c46d901b892ec940e611820549218a4a7874a2e",
+            ),
+            (
+                HashAlgorithm::Sha3_512,
+                "7d2f2ead5311ed5a3d19195c5b7e21d821696dac882ed93b2851460edfaa57fcabfed0a3d92bb2c8e94cfd6a4d71c14b489d7b8963191a6a0ca20547d7373f5d",
+            ),
+            (
+                HashAlgorithm::Blake2b,
+                "f49edc3801732fd8f620c07074d7658303384b0e54832cf205d28da486dd2ba6c075f1a527161350e39a0160549d7de02e60f3ab043fda7dbc7bf02270a95f46",
+            ),
+            (
+                HashAlgorithm::Blake2s,
+                "5566964a1623a4d80a0ce72d120bf9a619081b6457db92f6c3bd56ce6b292e8e",
+            ),
+            (
+                HashAlgorithm::Blake3,
+                "368fe3d7b7d7f3fa0c99f90c847ef0297c2b6d072c814ab4eac2f0b2cd9096e5",
+            ),
+            (HashAlgorithm::Crc32, "e0117757"),
+            (HashAlgorithm::Crc32c, "a8dab577"),
+            (HashAlgorithm::Adler32, "0b0601e8"),
+            (HashAlgorithm::Xxh64, "befd8f8bb198c0dd"),
+            (HashAlgorithm::Xxh3_64, "75048302860d0ca2"),
+            (HashAlgorithm::Xxh3_128, "6bd1e980fe404b77de30e80c1e49befe"),
+        ];
+        for (algorithm, hex) in expected {
+            let hash = hash_file("examples/valid.txt", algorithm)
+                .expect("Expected valid.txt to hash successfully.");
+            assert_eq!(hash.to_string(), hex, "{algorithm} digest of valid.txt");
+            assert_eq!(
+                hash.to_hex().len(),
+                algorithm.output_len() * 2,
+                "{algorithm} digest length"
+            );
+        }
+    }
+
+    #[test]
+    fn test_algorithm_from_str() {
+        for algorithm in HashAlgorithm::ALL {
+            assert_eq!(
+                algorithm.name().parse(),
+                Ok(algorithm),
+                "round trip {algorithm}"
+            );
+        }
+        assert_eq!("sha256".parse(), Ok(HashAlgorithm::Sha256));
+        assert_eq!("SHA3_512".parse(), Ok(HashAlgorithm::Sha3_512));
+        assert_eq!("sha512-256".parse(), Ok(HashAlgorithm::Sha512_256));
+        assert_eq!("crc32c".parse(), Ok(HashAlgorithm::Crc32c));
+        assert_eq!("XXH128".parse(), Ok(HashAlgorithm::Xxh3_128));
+        assert!("whirlpool".parse::<HashAlgorithm>().is_err());
+    }
+
+    #[test]
+    fn test_multiple_algorithms() {
+        let algorithms = [
+            HashAlgorithm::Sha512,
+            HashAlgorithm::Sha256,
+            HashAlgorithm::Blake3,
+            HashAlgorithm::Sha256,
+        ];
+        let hashes = hash_file_multi("examples/valid.txt", &algorithms)
+            .expect("Expected valid.txt to hash successfully.");
+        assert_eq!(hashes.len(), 3, "duplicate algorithms are only hashed once");
+        for (algorithm, hash) in hashes {
+            let single = hash_file("examples/valid.txt", algorithm)
+                .expect("Expected valid.txt to hash successfully.");
+            assert_eq!(hash, single, "{algorithm} digest matches single-pass hash");
+        }
+    }
+
+    #[test]
+    fn test_reader_and_bytes() {
+        let contents = std::fs::read("examples/valid.txt").expect("valid.txt is readable");
+        for algorithm in HashAlgorithm::ALL {
+            let file = hash_file("examples/valid.txt", algorithm)
+                .expect("Expected valid.txt to hash successfully.");
+            let reader = hash_reader(contents.as_slice(), algorithm)
+                .expect("Expected a slice to hash successfully.");
+            assert_eq!(reader, file, "{algorithm} digest of a reader");
+            assert_eq!(
+                hash_bytes(&contents, algorithm),
+                file,
+                "{algorithm} digest of bytes"
+            );
+        }
+    }
+
+    #[test]
+    fn test_reader_error() {
+        struct Failing;
+
+        impl Read for Failing {
+            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("broken pipe"))
+            }
+        }
+
+        let err = hash_reader(Failing, HashAlgorithm::Sha256).expect_err("reader always fails");
+        assert!(
+            matches!(
+                err,
+                HashError::Read {
+                    path: None,
+                    offset: 0,
+                    ..
+                }
+            ),
+            "got {err:?}"
+        );
+    }
+
+    #[test]
+    fn test_progress() {
+        let mut reports = Vec::new();
+        let options = HashOptions::new().on_progress(|progress| reports.push(*progress));
+        hash_file_with("examples/valid.txt", &[HashAlgorithm::Sha256], options)
+            .expect("Expected valid.txt to hash successfully.");
+        let last = reports.last().expect("progress is reported at least once");
+        assert_eq!(last.bytes_processed, 10);
+        assert_eq!(last.total_bytes, Some(10));
+        assert_eq!(last.fraction(), Some(1.0));
+    }
+
+    #[test]
+    fn test_read_strategies() {
+        let path = std::env::temp_dir().join(format!("hash-checker-read-{}", std::process::id()));
+        // Larger than every buffer, and not a multiple of any of them.
+        let data: Vec<u8> = (0..9 * 1024 * 1024 + 7).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&path, &data).expect("Failed to write a temporary file");
+        let expected = hash_bytes(&data, HashAlgorithm::Sha256);
+        for strategy in ReadStrategy::ALL {
+            let options = HashOptions::new().read_strategy(strategy);
+            let hashes = hash_file_with(&path, &[HashAlgorithm::Sha256], options)
+                .expect("Expected the temporary file to hash.");
+            assert_eq!(hashes[&HashAlgorithm::Sha256], expected, "{strategy}");
+        }
+        std::fs::remove_file(&path).ok();
+
+        let options = HashOptions::new().read_strategy(ReadStrategy::Mmap);
+        let hashes = hash_file_with("examples/valid.txt", &[HashAlgorithm::Sha256], options)
+            .expect("Expected valid.txt to hash.");
+        assert_eq!(
+            hashes[&HashAlgorithm::Sha256].to_string(),
+            "6d78392a5886177fe5b86e585a0b695a2bcd01a05504b3c4e38bc8eeb21e8326"
+        );
+        assert_eq!("MMAP".parse(), Ok(ReadStrategy::Mmap));
+    }
+
+    #[test]
+    fn test_parallel_blake3() {
+        // Spans several parallel buffers, ending part way through one.
+        let data: Vec<u8> = (0..40 * 1024 * 1024 + 3).map(|i| (i % 253) as u8).collect();
+        let expected = hash_bytes(&data, HashAlgorithm::Blake3);
+        let algorithms = [HashAlgorithm::Blake3, HashAlgorithm::Sha256];
+        let options = HashOptions::new()
+            .parallel_blake3(0)
+            .total_bytes(data.len() as u64);
+        let hashes =
+            hash_reader_with(&*data, &algorithms, options).expect("reading a slice cannot fail");
+        assert_eq!(hashes[&HashAlgorithm::Blake3], expected);
+        assert_eq!(
+            hashes[&HashAlgorithm::Sha256],
+            hash_bytes(&data, HashAlgorithm::Sha256)
+        );
+    }
+
+    #[test]
+    fn test_cancellation() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let options = HashOptions::new().cancellation(token);
+        let err = hash_file_with("examples/valid.txt", &[HashAlgorithm::Sha256], options)
+            .expect_err("cancelled before the first read");
+        assert!(
+            matches!(err, HashError::Cancelled { offset: 0, .. }),
+            "got {err:?}"
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! Background hashing jobs for the GUI
+
+use crate::{CancellationToken, Digest, HashAlgorithm, HashError, HashOptions, Progress};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, mpsc};
+use std::time::Duration;
+use web_time::Instant;
+
+/// How often a running job reports its progress back to the UI.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Identifies a job started with [`Jobs::spawn`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JobId(u64);
+
+/// The data a job hashes.
+#[derive(Clone, Debug)]
+pub enum JobSource {
+    /// A file on disk, read by the worker.
+    Path(PathBuf),
+
+    /// Data already in memory, such as a file dropped onto the web app.
+    Bytes { name: String, data: Arc<[u8]> },
+}
+
+impl JobSource {
+    /// A name for the source to show to the user.
+    #[must_use]
+    pub fn name(&self) -> String {
+        match self {
+            Self::Path(path) => path.display().to_string(),
+            Self::Bytes { name, .. } => name.clone(),
+        }
+    }
+}
+
+/// A message sent from a running job back to the UI thread.
+#[derive(Debug)]
+pub enum JobEvent {
+    /// The job has hashed some more of its input.
+    Progress { id: JobId, progress: Progress },
+
+    /// The job has finished, successfully or not.
+    Finished {
+        id: JobId,
+        result: Result<BTreeMap<HashAlgorithm, Digest>, HashError>,
+    },
+}
+
+impl JobEvent {
+    /// The job this event is about.
+    #[must_use]
+    pub fn id(&self) -> JobId {
+        match self {
+            Self::Progress { id, .. } | Self::Finished { id, .. } => *id,
+        }
+    }
+}
+
+/// Runs hashes off the UI thread and collects their results.
+///
+/// On native targets jobs are queued on a pool with one worker thread per core, so dropping
+/// in thousands of files doesn't start thousands of threads. Workers report back through a
+/// channel and request a repaint whenever there is something new to show.
+/// Call [`Jobs::poll`] once per frame to receive the events.
+pub struct Jobs {
+    sender: mpsc::Sender<JobEvent>,
+    receiver: mpsc::Receiver<JobEvent>,
+    next_id: u64,
+    running: BTreeMap<JobId, CancellationToken>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pool: crate::batch::WorkerPool,
+}
+
+impl Default for Jobs {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            sender,
+            receiver,
+            next_id: 0,
+            running: BTreeMap::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            pool: crate::batch::WorkerPool::new(crate::BatchOptions::default()),
+        }
+    }
+}
+
+impl Jobs {
+    /// Queues `source` to be hashed with every one of `algorithms`.
+    ///
+    /// With `parallel_blake3`, sources of at least [`crate::PARALLEL_BLAKE3_MIN_LEN`] bytes are
+    /// hashed with BLAKE3 on every core.
+    pub fn spawn(
+        &mut self,
+        ctx: &egui::Context,
+        source: JobSource,
+        algorithms: Vec<HashAlgorithm>,
+        parallel_blake3: bool,
+    ) -> JobId {
+        let id = JobId(self.next_id);
+        self.next_id += 1;
+
+        let token = CancellationToken::new();
+        self.running.insert(id, token.clone());
+
+        let sender = self.sender.clone();
+        let ctx = ctx.clone();
+        let job = move || {
+            let mut last_report = Instant::now();
+            let mut options = HashOptions::new()
+                .cancellation(token)
+                .on_progress(|progress| {
+                    if last_report.elapsed() >= PROGRESS_INTERVAL {
+                        last_report = Instant::now();
+                        sender
+                            .send(JobEvent::Progress {
+                                id,
+                                progress: *progress,
+                            })
+                            .ok();
+                        ctx.request_repaint();
+                    }
+                });
+            if parallel_blake3 {
+                options = options.parallel_blake3(crate::PARALLEL_BLAKE3_MIN_LEN);
+            }
+            let result = match source {
+                JobSource::Path(path) => crate::hash_file_with(path, &algorithms, options),
+                JobSource::Bytes { data, .. } => crate::hash_reader_with(
+                    &*data,
+                    &algorithms,
+                    options.total_bytes(data.len() as u64),
+                ),
+            };
+            sender.send(JobEvent::Finished { id, result }).ok();
+            ctx.request_repaint();
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        self.pool.execute(job);
+
+        // There are no threads on the web, so the job runs to completion right away.
+        #[cfg(target_arch = "wasm32")]
+        job();
+
+        id
+    }
+
+    /// Asks a running job to stop. It will finish with [`HashError::Cancelled`].
+    pub fn cancel(&self, id: JobId) {
+        if let Some(token) = self.running.get(&id) {
+            token.cancel();
+        }
+    }
+
+    /// Asks every running job to stop.
+    pub fn cancel_all(&self) {
+        for token in self.running.values() {
+            token.cancel();
+        }
+    }
+
+    /// Returns every event sent by the jobs since the last call.
+    pub fn poll(&mut self) -> Vec<JobEvent> {
+        let events: Vec<JobEvent> = self.receiver.try_iter().collect();
+        for event in &events {
+            if let JobEvent::Finished { id, .. } = event {
+                self.running.remove(id);
+            }
+        }
+        events
+    }
+
+    /// Whether any job is still running.
+    #[must_use]
+    pub fn is_busy(&self) -> bool {
+        !self.running.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wait_for_finish(jobs: &mut Jobs) -> Vec<JobEvent> {
+        let start = Instant::now();
+        let mut events = Vec::new();
+        while jobs.is_busy() {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "job did not finish"
+            );
+            events.extend(jobs.poll());
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        events
+    }
+
+    #[test]
+    fn test_job_finishes() {
+        let ctx = egui::Context::default();
+        let mut jobs = Jobs::default();
+        let id = jobs.spawn(
+            &ctx,
+            JobSource::Path(PathBuf::from("examples/valid.txt")),
+            vec![HashAlgorithm::Sha256],
+            false,
+        );
+        let events = wait_for_finish(&mut jobs);
+        let Some(JobEvent::Finished {
+            id: finished,
+            result,
+        }) = events.into_iter().last()
+        else {
+            panic!("the last event is the job finishing");
+        };
+        assert_eq!(finished, id);
+        let hashes = result.expect("Expected valid.txt to hash successfully.");
+        assert_eq!(
+            hashes[&HashAlgorithm::Sha256].to_string(),
+            "6d78392a5886177fe5b86e585a0b695a2bcd01a05504b3c4e38bc8eeb21e8326"
+        );
+    }
+
+    #[test]
+    fn test_bytes_job() {
+        let ctx = egui::Context::default();
+        let mut jobs = Jobs::default();
+        jobs.spawn(
+            &ctx,
+            JobSource::Bytes {
+                name: "valid.txt".to_owned(),
+                data: Arc::from(&b"123456789\n"[..]),
+            },
+            vec![HashAlgorithm::Sha256],
+            false,
+        );
+        let events = wait_for_finish(&mut jobs);
+        let Some(JobEvent::Finished { result, .. }) = events.into_iter().last() else {
+            panic!("the last event is the job finishing");
+        };
+        let hashes = result.expect("Expected in-memory data to hash successfully.");
+        assert_eq!(
+            hashes[&HashAlgorithm::Sha256].to_string(),
+            "6d78392a5886177fe5b86e585a0b695a2bcd01a05504b3c4e38bc8eeb21e8326"
+        );
+    }
+}
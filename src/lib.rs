@@ -1,7 +1,62 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 mod app;
-pub use app::TemplateApp;
+mod batch;
+mod checksum_file;
+mod file_dialog;
+pub use app::HashCheckerApp;
+pub use batch::{Batch, BatchOptions, HashedPath, Ordered, hash_batch};
+pub use checksum_file::{
+    ChecksumEntry, ChecksumFile, EntryResult, EntryStatus, Summary, VerificationReport,
+    VerifyOptions, algorithm_for_file_name, verify_checksum_file, verify_signed_checksum_file,
+};
+
+mod digest;
+pub use digest::{Digest, ParseDigestError};
+
+mod expected;
+pub use expected::{Comparison, ExpectedDigest, verify_file};
+
+mod git;
+pub use git::{
+    ObjectFormat, UnknownObjectFormat, git_blob_id, git_blob_id_of, git_object_id, git_tree_id,
+};
+
+mod jobs;
+pub use jobs::{JobEvent, JobId, JobSource, Jobs};
+
+#[cfg(all(feature = "mmap", not(target_arch = "wasm32")))]
+mod mmap;
+
+mod manifest;
+pub use manifest::{Manifest, ManifestFormat, UnknownManifestFormat};
+
+mod multihash;
+pub use multihash::{Cid, MultihashError, RAW_CODEC, multihash_code, verify_cid};
+
+mod openpgp;
+mod signature;
+pub use signature::{
+    PublicKey, SSH_NAMESPACE, Signature, SignatureError, SignatureFormat, Signer, TrustedKey,
+    TrustedKeys, find_signature,
+};
+
+mod sri;
+pub use sri::{
+    Integrity, ParseIntegrityError, SRI_ALGORITHMS, integrity_for_file, verify_integrity,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod walk;
+#[cfg(not(target_arch = "wasm32"))]
+pub use walk::{HASH_IGNORE_FILE, WalkError, WalkOptions, hash_directory, hash_paths, walk};
 
 mod hashing;
-pub use hashing::hash_sha256;
+pub use hashing::{
+    CancellationToken, HashAlgorithm, HashError, HashOptions, PARALLEL_BLAKE3_MIN_LEN, Progress,
+    ReadStrategy, UnknownAlgorithm, UnknownReadStrategy, hash_bytes, hash_file, hash_file_multi,
+    hash_file_with, hash_reader, hash_reader_multi, hash_reader_with, hash_sha256,
+};
+
+#[cfg(test)]
+mod test_support;
@@ -1,15 +1,41 @@
 #![warn(clippy::all, rust_2018_idioms)]
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
+#[cfg(not(target_arch = "wasm32"))]
+mod cli;
+#[cfg(all(windows, not(debug_assertions)))]
+mod console;
+#[cfg(test)]
+#[path = "test_support.rs"]
+mod test_support;
+
 // When compiling natively:
 #[cfg(not(target_arch = "wasm32"))]
-fn main() -> eframe::Result {
+fn main() -> std::process::ExitCode {
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
 
+    // Any arguments mean we were run from a terminal or script, so skip the window.
+    if std::env::args_os().len() > 1 {
+        #[cfg(all(windows, not(debug_assertions)))]
+        console::attach_to_parent();
+        return cli::run();
+    }
+
+    match run_gui() {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(err) => {
+            log::error!("{err}");
+            std::process::ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn run_gui() -> eframe::Result {
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
-            .with_inner_size([400.0, 300.0])
-            .with_min_inner_size([300.0, 220.0])
+            .with_inner_size([720.0, 480.0])
+            .with_min_inner_size([400.0, 300.0])
             .with_icon(
                 // NOTE: Adding an icon is optional
                 eframe::icon_data::from_png_bytes(&include_bytes!("../assets/icon-256.png")[..])
@@ -20,7 +46,7 @@ fn main() -> eframe::Result {
     eframe::run_native(
         "Hash Checker",
         native_options,
-        Box::new(|cc| Ok(Box::new(hash_checker::TemplateApp::new(cc)))),
+        Box::new(|cc| Ok(Box::new(hash_checker::HashCheckerApp::new(cc)))),
     )
 }
 
@@ -50,7 +76,7 @@ fn main() {
             .start(
                 canvas,
                 web_options,
-                Box::new(|cc| Ok(Box::new(eframe_template::TemplateApp::new(cc)))),
+                Box::new(|cc| Ok(Box::new(hash_checker::HashCheckerApp::new(cc)))),
             )
             .await;
 
@@ -0,0 +1,424 @@
+//! Writing checksum manifests such as `SHA256SUMS` and SFV files
+
+use crate::{Digest, HashAlgorithm, HashError};
+use log::warn;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::{Component, Path};
+use std::str::FromStr;
+
+/// The layout of a written [`Manifest`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum ManifestFormat {
+    /// `<hex>  <file>`, as written by `sha256sum`.
+    #[default]
+    Gnu,
+
+    /// `SHA256 (<file>) = <hex>`, as written by BSD `sha256` and `sha256sum --tag`.
+    Bsd,
+
+    /// A JSON document listing the path, algorithm and hex digest of every file.
+    Json,
+
+    /// `<file> <CRC-32>`, the Simple File Verification format. Only CRC-32 digests are written.
+    Sfv,
+}
+
+impl ManifestFormat {
+    pub const ALL: [Self; 4] = [Self::Gnu, Self::Bsd, Self::Json, Self::Sfv];
+
+    /// A human-readable name for the format.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Gnu => "GNU (sha256sum)",
+            Self::Bsd => "BSD tagged",
+            Self::Json => "JSON",
+            Self::Sfv => "SFV",
+        }
+    }
+
+    /// Whether the format can hold digests of `algorithm`.
+    #[must_use]
+    pub fn supports(self, algorithm: HashAlgorithm) -> bool {
+        self != Self::Sfv || algorithm == HashAlgorithm::Crc32
+    }
+
+    /// Whether each line or entry names its algorithm, so the manifest can hold digests of
+    /// more than one. Checkers guess the algorithm of untagged GNU lines from their length.
+    #[must_use]
+    pub fn is_tagged(self) -> bool {
+        matches!(self, Self::Bsd | Self::Json)
+    }
+
+    /// The conventional name of a manifest in this format holding digests of `algorithm`,
+    /// e.g. `SHA256SUMS`.
+    #[must_use]
+    pub fn file_name(self, algorithm: HashAlgorithm) -> String {
+        let tag = algorithm.bsd_tag().replace('-', "");
+        match self {
+            Self::Gnu | Self::Bsd => format!("{}SUMS", tag.to_ascii_uppercase()),
+            Self::Json => format!("{}SUMS.json", tag.to_ascii_uppercase()),
+            Self::Sfv => "checksums.sfv".to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for ManifestFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Gnu => "gnu",
+            Self::Bsd => "bsd",
+            Self::Json => "json",
+            Self::Sfv => "sfv",
+        })
+    }
+}
+
+impl FromStr for ManifestFormat {
+    type Err = UnknownManifestFormat;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "gnu" | "sha256sum" => Ok(Self::Gnu),
+            "bsd" | "tag" => Ok(Self::Bsd),
+            "json" => Ok(Self::Json),
+            "sfv" => Ok(Self::Sfv),
+            _ => Err(UnknownManifestFormat(s.to_owned())),
+        }
+    }
+}
+
+/// The error returned when parsing an unrecognised [`ManifestFormat`] name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownManifestFormat(pub String);
+
+impl fmt::Display for UnknownManifestFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown manifest format {:?} (expected gnu, bsd, json or sfv)",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for UnknownManifestFormat {}
+
+/// A list of files and their digests, ready to be written out as a checksum file.
+///
+/// Paths are stored relative to a base directory with `/` separators and are always written
+/// in sorted order, so the same files produce byte-for-byte identical manifests on every
+/// platform.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Manifest {
+    entries: BTreeMap<String, Vec<Digest>>,
+}
+
+impl Manifest {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the digest of the file at `path`, which is recorded relative to `base_dir`.
+    ///
+    /// Paths outside `base_dir` are recorded as given.
+    pub fn insert(&mut self, base_dir: &Path, path: &Path, digest: Digest) {
+        let digests = self
+            .entries
+            .entry(relative_path(base_dir, path))
+            .or_default();
+        digests.retain(|existing| existing.algorithm() != digest.algorithm());
+        digests.push(digest);
+        digests.sort_by_key(Digest::algorithm);
+    }
+
+    /// Hashes each of `files` with every one of `algorithms`, recording their paths relative
+    /// to `base_dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered while hashing.
+    pub fn hash_files(
+        base_dir: &Path,
+        files: impl IntoIterator<Item = impl AsRef<Path>>,
+        algorithms: &[HashAlgorithm],
+    ) -> Result<Self, HashError> {
+        let mut manifest = Self::new();
+        for file in files {
+            let file = file.as_ref();
+            for (_, digest) in crate::hash_file_multi(file, algorithms)? {
+                manifest.insert(base_dir, file, digest);
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Hashes each of `paths` with every one of `algorithms`, walking directories with
+    /// `options`, and records their paths relative to `base_dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered while listing or hashing files.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn hash_paths(
+        base_dir: &Path,
+        paths: Vec<std::path::PathBuf>,
+        algorithms: &[HashAlgorithm],
+        options: &crate::WalkOptions,
+        batch: &crate::BatchOptions,
+    ) -> Result<Self, crate::WalkError> {
+        let mut manifest = Self::new();
+        for result in crate::hash_paths(paths, algorithms, options, batch)? {
+            let (file, hashes) = result?;
+            for digest in hashes.into_values() {
+                manifest.insert(base_dir, &file, digest);
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Hashes every file under `dir`, recursively, recording paths relative to `dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered while listing or hashing files.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use hash_checker::{HashAlgorithm, Manifest, ManifestFormat};
+    /// let manifest = Manifest::hash_directory("examples".as_ref(), &[HashAlgorithm::Sha256])
+    ///     .expect("examples is readable");
+    /// assert!(manifest.render(ManifestFormat::Gnu).contains("  valid.txt\n"));
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn hash_directory(
+        dir: &Path,
+        algorithms: &[HashAlgorithm],
+    ) -> Result<Self, crate::WalkError> {
+        Self::hash_paths(
+            dir,
+            vec![dir.to_owned()],
+            algorithms,
+            &crate::WalkOptions::default(),
+            &crate::BatchOptions::default(),
+        )
+    }
+
+    /// Whether the manifest has no entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The number of files in the manifest.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Every path and digest in the manifest, sorted by path and then by algorithm.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Digest)> {
+        self.entries
+            .iter()
+            .flat_map(|(path, digests)| digests.iter().map(move |digest| (path.as_str(), digest)))
+    }
+
+    /// Writes the manifest to `writer` in the given format.
+    ///
+    /// Digests the format can't hold, such as SHA-256 digests in an SFV file, are left out,
+    /// as are files whose names SFV can't represent because they contain line breaks. GNU
+    /// lines don't name their algorithm, so a GNU manifest should only hold one; see
+    /// [`ManifestFormat::is_tagged`].
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from `writer`.
+    pub fn write_to(&self, mut writer: impl Write, format: ManifestFormat) -> io::Result<()> {
+        match format {
+            ManifestFormat::Gnu => {
+                for (path, digest) in self.iter() {
+                    let (prefix, path) = escape(path);
+                    writeln!(writer, "{prefix}{digest}  {path}")?;
+                }
+            }
+            ManifestFormat::Bsd => {
+                for (path, digest) in self.iter() {
+                    let (prefix, path) = escape(path);
+                    let tag = digest.algorithm().bsd_tag();
+                    writeln!(writer, "{prefix}{tag} ({path}) = {digest}")?;
+                }
+            }
+            ManifestFormat::Json => {
+                let files: Vec<JsonEntry<'_>> = self
+                    .iter()
+                    .map(|(path, digest)| JsonEntry {
+                        path,
+                        algorithm: digest.algorithm().id(),
+                        digest: digest.to_hex(),
+                    })
+                    .collect();
+                serde_json::to_writer_pretty(&mut writer, &JsonManifest { files })?;
+                writeln!(writer)?;
+            }
+            ManifestFormat::Sfv => {
+                for (path, digest) in self.iter() {
+                    if !format.supports(digest.algorithm()) {
+                        continue;
+                    }
+                    if path.contains(['\n', '\r']) {
+                        warn!("Leaving {path:?} out of the SFV file: SFV can't hold line breaks");
+                        continue;
+                    }
+                    writeln!(writer, "{path} {}", digest.to_hex().to_ascii_uppercase())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The manifest as it would be written by [`Manifest::write_to`].
+    #[must_use]
+    pub fn render(&self, format: ManifestFormat) -> String {
+        let mut buffer = Vec::new();
+        self.write_to(&mut buffer, format)
+            .expect("writing to a Vec cannot fail");
+        String::from_utf8(buffer).expect("manifests are written as UTF-8")
+    }
+}
+
+#[derive(serde::Serialize)]
+struct JsonManifest<'a> {
+    files: Vec<JsonEntry<'a>>,
+}
+
+#[derive(serde::Serialize)]
+struct JsonEntry<'a> {
+    path: &'a str,
+    algorithm: &'static str,
+    digest: String,
+}
+
+/// Escapes backslashes and line breaks the way GNU tools do, returning the `\` line prefix
+/// that marks an escaped name alongside the name itself.
+fn escape(path: &str) -> (&'static str, std::borrow::Cow<'_, str>) {
+    if !path.contains(['\\', '\n', '\r']) {
+        return ("", path.into());
+    }
+    let escaped = path
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r");
+    ("\\", escaped.into())
+}
+
+/// `path` relative to `base_dir`, with `/` separators on every platform.
+fn relative_path(base_dir: &Path, path: &Path) -> String {
+    let (base_dir, path) = (without_cur_dir(base_dir), without_cur_dir(path));
+    let relative = path.strip_prefix(&base_dir).unwrap_or(&path);
+    let parts: Vec<_> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect();
+    parts.join("/")
+}
+
+/// `path` without any `.` components, so `./dist/a.txt` is recognised as being in `dist`.
+fn without_cur_dir(path: &Path) -> std::path::PathBuf {
+    path.components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sha256(hex: &str) -> Digest {
+        Digest::parse(HashAlgorithm::Sha256, hex).expect("valid digest")
+    }
+
+    const VALID_SHA256: &str = "6d78392a5886177fe5b86e585a0b695a2bcd01a05504b3c4e38bc8eeb21e8326";
+
+    #[test]
+    fn test_formats() {
+        let base = Path::new("dist");
+        let mut manifest = Manifest::new();
+        manifest.insert(base, &base.join("b.txt"), sha256(VALID_SHA256));
+        manifest.insert(base, &base.join("sub").join("a.txt"), sha256(VALID_SHA256));
+        manifest.insert(base, &base.join("new\nline"), sha256(VALID_SHA256));
+        // Spelled differently, but still inside `dist`.
+        manifest.insert(base, Path::new("./dist/sub/a.txt"), sha256(VALID_SHA256));
+
+        assert_eq!(
+            manifest.render(ManifestFormat::Gnu),
+            format!(
+                "{VALID_SHA256}  b.txt\n\
+                 \\{VALID_SHA256}  new\\nline\n\
+                 {VALID_SHA256}  sub/a.txt\n"
+            )
+        );
+        assert_eq!(
+            manifest.render(ManifestFormat::Bsd),
+            format!(
+                "SHA256 (b.txt) = {VALID_SHA256}\n\
+                 \\SHA256 (new\\nline) = {VALID_SHA256}\n\
+                 SHA256 (sub/a.txt) = {VALID_SHA256}\n"
+            )
+        );
+
+        let mut sfv = manifest.clone();
+        let crc32 = Digest::parse(HashAlgorithm::Crc32, "e0117757").expect("valid digest");
+        sfv.insert(base, &base.join("b.txt"), crc32.clone());
+        sfv.insert(base, &base.join("new\nline"), crc32);
+        assert_eq!(sfv.render(ManifestFormat::Sfv), "b.txt E0117757\n");
+
+        let json: serde_json::Value =
+            serde_json::from_str(&manifest.render(ManifestFormat::Json)).expect("valid JSON");
+        assert_eq!(json["files"][2]["path"], "sub/a.txt");
+        assert_eq!(json["files"][2]["algorithm"], "sha256");
+        assert_eq!(json["files"][2]["digest"], VALID_SHA256);
+    }
+
+    #[test]
+    fn test_sfv_round_trip() {
+        let manifest = Manifest::hash_directory(Path::new("examples"), &[HashAlgorithm::Crc32])
+            .expect("Expected examples to be readable.");
+        let parsed = crate::ChecksumFile::parse_sfv(&manifest.render(ManifestFormat::Sfv));
+        assert!(parsed.malformed_lines.is_empty());
+        assert_eq!(parsed.entries.len(), manifest.len());
+        let report = parsed.verify(Path::new("examples"), crate::VerifyOptions::default());
+        assert!(report.is_success(), "{:?}", report.summary());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let manifest = Manifest::hash_directory(Path::new("examples"), &[HashAlgorithm::Sha256])
+            .expect("Expected examples to be readable.");
+        for format in [ManifestFormat::Gnu, ManifestFormat::Bsd] {
+            let parsed = crate::ChecksumFile::parse(&manifest.render(format), None);
+            assert!(parsed.malformed_lines.is_empty(), "{format}");
+            let report = parsed.verify(Path::new("examples"), crate::VerifyOptions::default());
+            assert!(report.is_success(), "{format}: {:?}", report.summary());
+        }
+    }
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(
+            ManifestFormat::Gnu.file_name(HashAlgorithm::Sha256),
+            "SHA256SUMS"
+        );
+        assert_eq!(
+            ManifestFormat::Json.file_name(HashAlgorithm::Sha512_256),
+            "SHA512256SUMS.json"
+        );
+        assert_eq!(
+            ManifestFormat::Bsd.file_name(HashAlgorithm::Blake2b),
+            "BLAKE2BSUMS"
+        );
+    }
+}
@@ -0,0 +1,21 @@
+//! Memory-mapped file access, the only unsafe code in the crate
+
+use std::fs::File;
+use std::io;
+
+/// Maps the whole of `file` into memory, read-only.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be mapped, e.g. because it is a pipe or empty.
+#[expect(
+    unsafe_code,
+    reason = "memmap2 can't promise the file won't change while it is mapped"
+)]
+pub(crate) fn map(file: &File) -> io::Result<memmap2::Mmap> {
+    // SAFETY: The map is only read while hashing and never handed out beyond that. If another
+    // process truncates the file meanwhile, reads past its new end raise SIGBUS, which is why
+    // mapping is opt-in through `ReadStrategy::Mmap` rather than the default. Concurrent writes
+    // that don't truncate only change the digest, just as they would with ordinary reads.
+    unsafe { memmap2::Mmap::map(file) }
+}
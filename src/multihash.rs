@@ -0,0 +1,404 @@
+//! Multihashes and CIDs, as used by IPFS and other content-addressed stores
+
+use crate::{Comparison, Digest, HashAlgorithm, HashError};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The multicodec of a CID naming a single block of raw bytes, such as a whole file.
+pub const RAW_CODEC: u64 = 0x55;
+
+/// The multibase prefix of lowercase, unpadded Base32, the default encoding of CIDv1.
+const BASE32_PREFIX: char = 'b';
+
+/// The multihash code identifying `algorithm`, if it has one.
+///
+/// Checksums such as CRC-32 and xxHash are left out, as content addressing relies on
+/// collision resistance.
+#[must_use]
+pub fn multihash_code(algorithm: HashAlgorithm) -> Option<u64> {
+    match algorithm {
+        HashAlgorithm::Md5 => Some(0xd5),
+        HashAlgorithm::Sha1 => Some(0x11),
+        HashAlgorithm::Sha224 => Some(0x1013),
+        HashAlgorithm::Sha256 => Some(0x12),
+        HashAlgorithm::Sha384 => Some(0x20),
+        HashAlgorithm::Sha512 => Some(0x13),
+        HashAlgorithm::Sha512_256 => Some(0x1014),
+        HashAlgorithm::Sha3_256 => Some(0x16),
+        HashAlgorithm::Sha3_512 => Some(0x14),
+        HashAlgorithm::Blake2b => Some(0xb240),
+        HashAlgorithm::Blake2s => Some(0xb260),
+        HashAlgorithm::Blake3 => Some(0x1e),
+        HashAlgorithm::Crc32
+        | HashAlgorithm::Crc32c
+        | HashAlgorithm::Adler32
+        | HashAlgorithm::Xxh64
+        | HashAlgorithm::Xxh3_64
+        | HashAlgorithm::Xxh3_128 => None,
+    }
+}
+
+impl Digest {
+    /// Encodes the digest as a multihash: the algorithm's code and the digest length as
+    /// unsigned varints, followed by the digest.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MultihashError::Unsupported`] if the algorithm has no multihash code.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use hash_checker::{HashAlgorithm, hash_bytes};
+    /// let multihash = hash_bytes(b"", HashAlgorithm::Sha256).to_multihash().expect("sha2-256");
+    /// assert_eq!(multihash[..2], [0x12, 0x20]);
+    /// ```
+    pub fn to_multihash(&self) -> Result<Vec<u8>, MultihashError> {
+        let code = multihash_code(self.algorithm())
+            .ok_or(MultihashError::Unsupported(self.algorithm()))?;
+        let mut multihash = Vec::with_capacity(self.as_bytes().len() + 4);
+        write_varint(&mut multihash, code);
+        write_varint(&mut multihash, self.as_bytes().len() as u64);
+        multihash.extend_from_slice(self.as_bytes());
+        Ok(multihash)
+    }
+
+    /// Decodes a multihash, which must hold nothing else.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the multihash is malformed, uses an unsupported algorithm, or holds
+    /// a truncated digest.
+    pub fn from_multihash(multihash: &[u8]) -> Result<Self, MultihashError> {
+        let mut rest = multihash;
+        let digest = read_multihash(&mut rest)?;
+        if !rest.is_empty() {
+            return Err(MultihashError::Malformed("trailing bytes after multihash"));
+        }
+        Ok(digest)
+    }
+}
+
+/// A version 1 content identifier (CID) of raw bytes, as used to address a single block.
+///
+/// CIDs are displayed and parsed in Base32, the default for CIDv1. Only the raw codec is
+/// accepted: other codecs, such as the `dag-pb` of files added to IPFS in chunks, name an
+/// encoding of the content rather than the content itself, so no file hashes to them.
+///
+/// # Examples
+///
+/// ```rust
+/// use hash_checker::{Cid, HashAlgorithm, hash_bytes};
+/// let cid = Cid::new(hash_bytes(b"", HashAlgorithm::Sha256)).expect("sha2-256");
+/// assert_eq!(
+///     cid.to_string(),
+///     "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku"
+/// );
+/// assert_eq!(cid.to_string().parse::<Cid>(), Ok(cid));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cid {
+    digest: Digest,
+}
+
+impl Cid {
+    /// The CID of content with `digest`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MultihashError::Unsupported`] if the algorithm has no multihash code.
+    pub fn new(digest: Digest) -> Result<Self, MultihashError> {
+        if multihash_code(digest.algorithm()).is_none() {
+            return Err(MultihashError::Unsupported(digest.algorithm()));
+        }
+        Ok(Self { digest })
+    }
+
+    /// The digest of the content the CID names.
+    #[must_use]
+    pub fn digest(&self) -> &Digest {
+        &self.digest
+    }
+
+    /// The binary form of the CID: version, codec and multihash.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_varint(&mut bytes, 1);
+        write_varint(&mut bytes, RAW_CODEC);
+        bytes.extend(
+            self.digest
+                .to_multihash()
+                .expect("CIDs only hold digests with a multihash code"),
+        );
+        bytes
+    }
+
+    /// Parses the binary form of a CID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bytes` is not a CIDv1 of raw content with a supported multihash.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MultihashError> {
+        let mut rest = bytes;
+        // A CIDv0 is a bare sha2-256 multihash, always in Base58 and always of `dag-pb`.
+        match read_varint(&mut rest)? {
+            1 => {}
+            0x12 => return Err(MultihashError::Malformed("CIDv0 does not name raw content")),
+            _ => return Err(MultihashError::Malformed("unknown CID version")),
+        }
+        let codec = read_varint(&mut rest)?;
+        if codec != RAW_CODEC {
+            return Err(MultihashError::Codec(codec));
+        }
+        Ok(Self {
+            digest: Digest::from_multihash(rest)?,
+        })
+    }
+}
+
+impl fmt::Display for Cid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let encoded = data_encoding::BASE32_NOPAD.encode(&self.to_bytes());
+        write!(f, "{BASE32_PREFIX}{}", encoded.to_ascii_lowercase())
+    }
+}
+
+impl FromStr for Cid {
+    type Err = MultihashError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let Some(encoded) = s
+            .strip_prefix(BASE32_PREFIX)
+            .or_else(|| s.strip_prefix(BASE32_PREFIX.to_ascii_uppercase()))
+        else {
+            return Err(MultihashError::Malformed(
+                "only Base32 CIDs, starting with `b`, are supported",
+            ));
+        };
+        let bytes = data_encoding::BASE32_NOPAD
+            .decode(encoded.to_ascii_uppercase().as_bytes())
+            .map_err(|_invalid| MultihashError::Malformed("CID is not valid Base32"))?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+/// The error returned when a multihash or CID cannot be encoded or decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MultihashError {
+    /// The algorithm has no multihash code, or it isn't supported here.
+    Unsupported(HashAlgorithm),
+
+    /// The multihash code is not one of a supported algorithm.
+    UnknownCode(u64),
+
+    /// The CID names something other than raw bytes.
+    Codec(u64),
+
+    /// The digest is shorter or longer than the algorithm's output, e.g. truncated.
+    Length {
+        algorithm: HashAlgorithm,
+        actual: usize,
+    },
+
+    /// The bytes are not a well-formed multihash or CID.
+    Malformed(&'static str),
+}
+
+impl fmt::Display for MultihashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported(algorithm) => write!(f, "{algorithm} has no multihash code"),
+            Self::UnknownCode(code) => write!(f, "unsupported multihash code {code:#x}"),
+            Self::Codec(codec) => write!(
+                f,
+                "CID codec {codec:#x} is not raw ({RAW_CODEC:#x}), so it doesn't name a file's contents"
+            ),
+            Self::Length { algorithm, actual } => write!(
+                f,
+                "{algorithm} digests are {} bytes long, got {actual}",
+                algorithm.output_len()
+            ),
+            Self::Malformed(reason) => f.write_str(reason),
+        }
+    }
+}
+
+impl std::error::Error for MultihashError {}
+
+/// Hashes the file at `path` with the algorithm of `cid`, and reports whether its contents are
+/// what the CID names.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be hashed; see [`crate::hash_file_multi`].
+///
+/// # Examples
+///
+/// ```rust
+/// use hash_checker::{Cid, Comparison, HashAlgorithm, verify_cid};
+/// let cid: Cid = "bafkreidnpa4suwegc576lodolbnaw2k2fpgqdicvasz4jy4lzdxlehudey"
+///     .parse()
+///     .expect("valid CID");
+/// let comparison = verify_cid("examples/valid.txt", &cid).expect("valid.txt is readable");
+/// assert_eq!(comparison, Comparison::Match(HashAlgorithm::Sha256));
+/// ```
+pub fn verify_cid(path: impl AsRef<Path>, cid: &Cid) -> Result<Comparison, HashError> {
+    let algorithm = cid.digest.algorithm();
+    let hashes = crate::hash_file_multi(path, &[algorithm])?;
+    Ok(if hashes.get(&algorithm) == Some(&cid.digest) {
+        Comparison::Match(algorithm)
+    } else {
+        Comparison::Mismatch
+    })
+}
+
+/// Reads a multihash from the start of `bytes`, leaving the rest.
+fn read_multihash(bytes: &mut &[u8]) -> Result<Digest, MultihashError> {
+    let code = read_varint(bytes)?;
+    let algorithm = HashAlgorithm::ALL
+        .into_iter()
+        .find(|algorithm| multihash_code(*algorithm) == Some(code))
+        .ok_or(MultihashError::UnknownCode(code))?;
+    let len = usize::try_from(read_varint(bytes)?)
+        .map_err(|_overflow| MultihashError::Malformed("multihash is truncated"))?;
+    let (digest, rest) = bytes
+        .split_at_checked(len)
+        .ok_or(MultihashError::Malformed("multihash is truncated"))?;
+    *bytes = rest;
+    Digest::new(algorithm, digest).map_err(|_length| MultihashError::Length {
+        algorithm,
+        actual: len,
+    })
+}
+
+/// Appends `value` as an unsigned LEB128 varint.
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Reads an unsigned varint from the start of `bytes`, leaving the rest.
+///
+/// The multiformats spec limits varints to 9 bytes and requires the shortest encoding, so that
+/// every value has exactly one.
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, MultihashError> {
+    let mut value = 0;
+    for (index, byte) in bytes.iter().take(9).enumerate() {
+        value |= u64::from(byte & 0x7f) << (7 * index);
+        if byte & 0x80 == 0 {
+            if *byte == 0 && index > 0 {
+                return Err(MultihashError::Malformed("varint is not minimally encoded"));
+            }
+            *bytes = &bytes[index + 1..];
+            return Ok(value);
+        }
+    }
+    Err(MultihashError::Malformed("varint is truncated or too long"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_bytes;
+
+    #[test]
+    fn test_multihash_round_trip() {
+        for algorithm in HashAlgorithm::ALL {
+            let digest = hash_bytes(b"hello world", algorithm);
+            let Some(code) = multihash_code(algorithm) else {
+                assert_eq!(
+                    digest.to_multihash(),
+                    Err(MultihashError::Unsupported(algorithm))
+                );
+                continue;
+            };
+            let multihash = digest.to_multihash().expect("supported");
+            let mut prefix = Vec::new();
+            write_varint(&mut prefix, code);
+            assert!(multihash.starts_with(&prefix), "{algorithm} code");
+            assert_eq!(
+                Digest::from_multihash(&multihash),
+                Ok(digest.clone()),
+                "{algorithm}"
+            );
+
+            let cid = Cid::new(digest).expect("supported");
+            assert_eq!(cid.to_string().parse(), Ok(cid.clone()), "{algorithm}");
+            assert_eq!(
+                cid.to_string().to_ascii_uppercase().parse(),
+                Ok(cid),
+                "{algorithm}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_known_values() {
+        // As printed by `ipfs cid hashes` and the multihash spec's examples.
+        let multihash = hash_bytes(b"foo", HashAlgorithm::Sha256)
+            .to_multihash()
+            .expect("supported");
+        assert_eq!(
+            data_encoding::HEXLOWER.encode(&multihash),
+            "12202c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae"
+        );
+        let multihash = hash_bytes(b"foo", HashAlgorithm::Blake2b)
+            .to_multihash()
+            .expect("supported");
+        assert_eq!(multihash[..3], [0xc0, 0xe4, 0x02]);
+        assert_eq!(multihash[3], 64);
+
+        let cid: Cid = "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku"
+            .parse()
+            .expect("valid CID");
+        assert_eq!(cid.digest(), &hash_bytes(b"", HashAlgorithm::Sha256));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let sha256 = hash_bytes(b"", HashAlgorithm::Sha256)
+            .to_multihash()
+            .expect("supported");
+        let cases: [(&[u8], MultihashError); 5] = [
+            (
+                &sha256[..20],
+                MultihashError::Malformed("multihash is truncated"),
+            ),
+            (
+                &[0x12, 0x10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+                MultihashError::Length {
+                    algorithm: HashAlgorithm::Sha256,
+                    actual: 16,
+                },
+            ),
+            (&[0x00, 0x00], MultihashError::UnknownCode(0)),
+            (
+                &[0x92, 0x00, 0x00],
+                MultihashError::Malformed("varint is not minimally encoded"),
+            ),
+            (
+                &[0xff; 10],
+                MultihashError::Malformed("varint is truncated or too long"),
+            ),
+        ];
+        for (multihash, error) in cases {
+            assert_eq!(Digest::from_multihash(multihash), Err(error));
+        }
+
+        // Files added to IPFS in chunks are `dag-pb` (0x70), which no file hashes to.
+        let mut dag_pb = vec![0x01, 0x70];
+        dag_pb.extend(&sha256);
+        assert_eq!(Cid::from_bytes(&dag_pb), Err(MultihashError::Codec(0x70)));
+        assert!(
+            "QmbWqxBEKC3P8tqsKc98xmWNzrzDtRLMiMPL8wBuTGsMnR"
+                .parse::<Cid>()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,330 @@
+//! OpenPGP keys and signatures, as made by `gpg`
+
+use crate::SignatureError;
+use pgp::composed::{
+    CleartextSignedMessage, Deserializable as _, DetachedSignature, SignedPublicKey,
+};
+use pgp::packet::{Signature, SignatureType, SubpacketData};
+use pgp::types::{KeyDetails, PublicKeyTrait, Tag};
+
+/// The first line of a clearsigned message, such as Fedora's `CHECKSUM` files.
+const CLEARSIGN_BEGIN: &str = "-----BEGIN PGP SIGNED MESSAGE-----";
+
+/// How every ASCII-armored OpenPGP block starts.
+const ARMOR_BEGIN: &str = "-----BEGIN PGP ";
+
+/// Whether `contents` is ASCII-armored OpenPGP data.
+pub(crate) fn is_armored(contents: &[u8]) -> bool {
+    contents
+        .trim_ascii_start()
+        .starts_with(ARMOR_BEGIN.as_bytes())
+}
+
+/// Whether `contents` is OpenPGP data, armored or binary.
+///
+/// Binary OpenPGP data starts with a packet header, which always has its top bit set. No text
+/// file does, unless it starts with a multi-byte UTF-8 character.
+pub(crate) fn is_openpgp(contents: &[u8]) -> bool {
+    is_armored(contents) || contents.first().is_some_and(|byte| byte & 0x80 != 0)
+}
+
+/// Whether `contents` is a clearsigned message, carrying its own signed text.
+pub(crate) fn is_clearsigned(contents: &[u8]) -> bool {
+    contents
+        .trim_ascii_start()
+        .starts_with(CLEARSIGN_BEGIN.as_bytes())
+}
+
+/// The text signed in a clearsigned message, with dash-escaping undone and `\r\n` line endings,
+/// and how many lines of armor precede it. `None` if `text` is not a valid clearsigned message.
+pub(crate) fn signed_text(text: &str) -> Option<(usize, String)> {
+    if !is_clearsigned(text.as_bytes()) {
+        return None;
+    }
+    let (message, _headers) = CleartextSignedMessage::from_string(text).ok()?;
+    // The armor headers end at the first blank line.
+    let armor_lines = text.lines().position(|line| line.trim().is_empty())? + 1;
+    Some((armor_lines, message.signed_text()))
+}
+
+/// Parses every certificate in an armored or binary key file.
+pub(crate) fn parse_keys(contents: &[u8]) -> Result<Vec<SignedPublicKey>, SignatureError> {
+    let (keys, _headers) = SignedPublicKey::from_reader_many(contents)
+        .map_err(|_invalid| SignatureError::Malformed("invalid OpenPGP public key"))?;
+    let keys: Vec<SignedPublicKey> = keys
+        .collect::<Result<_, _>>()
+        .map_err(|_invalid| SignatureError::Malformed("invalid OpenPGP public key"))?;
+    if keys.is_empty() {
+        return Err(SignatureError::Malformed("no public key found"));
+    }
+    Ok(keys)
+}
+
+/// Parses an armored or binary detached signature, or a clearsigned message and the text it
+/// signs.
+pub(crate) fn parse_signatures(
+    contents: &[u8],
+) -> Result<(Vec<Signature>, Option<String>), SignatureError> {
+    if is_clearsigned(contents) {
+        let (message, _headers) = CleartextSignedMessage::from_armor(contents)
+            .map_err(|_invalid| SignatureError::Malformed("invalid clearsigned message"))?;
+        return Ok((message.signatures().to_vec(), Some(message.signed_text())));
+    }
+    let (signatures, _headers) = DetachedSignature::from_reader_many(contents)
+        .map_err(|_invalid| SignatureError::Malformed("invalid OpenPGP signature"))?;
+    let signatures: Vec<Signature> = signatures
+        .map(|signature| signature.map(|detached| detached.signature))
+        .collect::<Result<_, _>>()
+        .map_err(|_invalid| SignatureError::Malformed("invalid OpenPGP signature"))?;
+    if signatures.is_empty() {
+        return Err(SignatureError::Malformed("no OpenPGP signature found"));
+    }
+    Ok((signatures, None))
+}
+
+/// The fingerprint of the certificate's primary key, as printed by `gpg --fingerprint` but
+/// without the spaces.
+pub(crate) fn fingerprint(key: &SignedPublicKey) -> String {
+    format!("{:X}", key.primary_key.fingerprint())
+}
+
+/// The first user ID the key holder has vouched for, e.g. `Jane Doe <jane@example.com>`.
+pub(crate) fn user_id(key: &SignedPublicKey) -> String {
+    key.details
+        .users
+        .iter()
+        .find(|user| {
+            user.signatures.iter().any(|signature| {
+                signature
+                    .verify_certification(&key.primary_key, Tag::UserId, &user.id)
+                    .is_ok()
+            })
+        })
+        .map(|user| String::from_utf8_lossy(user.id.id()).into_owned())
+        .unwrap_or_default()
+}
+
+/// How the key that made `signature` is identified, for reporting untrusted signatures.
+pub(crate) fn issuer(signature: &Signature) -> String {
+    if let Some(fingerprint) = signature.issuer_fingerprint().first() {
+        return format!("{fingerprint:X}");
+    }
+    signature
+        .issuer()
+        .first()
+        .map(|key_id| key_id.to_string().to_uppercase())
+        .unwrap_or_else(|| "(unknown)".to_owned())
+}
+
+/// Checks `signature` over `message` against the primary key and signing subkeys of `key`.
+///
+/// Returns `None` if none of them made the signature.
+pub(crate) fn verify(
+    key: &SignedPublicKey,
+    signature: &Signature,
+    message: &[u8],
+) -> Option<Result<(), SignatureError>> {
+    if !matches!(
+        signature.typ(),
+        Some(SignatureType::Binary | SignatureType::Text)
+    ) {
+        return Some(Err(SignatureError::Malformed(
+            "OpenPGP signature is not over a document",
+        )));
+    }
+    let primary = &key.primary_key;
+    if issued_by(signature, primary) {
+        if !primary_can_sign(key) {
+            return Some(Err(SignatureError::UnusableKey {
+                fingerprint: fingerprint(key),
+            }));
+        }
+        return Some(check(key, primary, signature, message));
+    }
+    let subkey = key
+        .public_subkeys
+        .iter()
+        .find(|subkey| issued_by(signature, &subkey.key))?;
+    // A subkey only speaks for the certificate if the primary key bound it, for signing, and
+    // hasn't revoked it since. Only the newest binding counts, as rebinding is how flags and
+    // expiry are changed.
+    let binding = subkey
+        .signatures
+        .iter()
+        .filter(|binding| {
+            binding.typ() == Some(SignatureType::SubkeyBinding)
+                && binding.verify_subkey_binding(primary, &subkey.key).is_ok()
+        })
+        .max_by_key(|binding| binding.created().copied());
+    let revoked = subkey.signatures.iter().any(|revocation| {
+        revocation.typ() == Some(SignatureType::SubkeyRevocation)
+            && revocation
+                .verify_subkey_binding(primary, &subkey.key)
+                .is_ok()
+    });
+    let usable = binding.is_some_and(|binding| {
+        // A zero expiration time means the subkey never expires.
+        let expires_at = binding
+            .key_expiration_time()
+            .filter(|validity| !validity.is_zero())
+            .map(|validity| *subkey.key.created_at() + *validity);
+        let expired = match (expires_at, signature.created()) {
+            (Some(expires_at), Some(created)) => *created > expires_at,
+            _ => false,
+        };
+        binding.key_flags().sign() && !expired
+    });
+    if !usable || revoked {
+        return Some(Err(SignatureError::UnusableKey {
+            fingerprint: format!("{:X}", subkey.key.fingerprint()),
+        }));
+    }
+    Some(check(key, &subkey.key, signature, message))
+}
+
+/// Whether the primary key of `key` may sign documents, going by the key flags of its newest
+/// self-signature. Keys made before key flags existed carry none, and may sign.
+fn primary_can_sign(key: &SignedPublicKey) -> bool {
+    let primary = &key.primary_key;
+    let direct = key
+        .details
+        .direct_signatures
+        .iter()
+        .filter(|signature| signature.verify_key(primary).is_ok());
+    let certifications = key.details.users.iter().flat_map(|user| {
+        user.signatures.iter().filter(|signature| {
+            signature
+                .verify_certification(primary, Tag::UserId, &user.id)
+                .is_ok()
+        })
+    });
+    direct
+        .chain(certifications)
+        .filter(|signature| {
+            signature.config().is_some_and(|config| {
+                config
+                    .hashed_subpackets()
+                    .any(|subpacket| matches!(subpacket.data, SubpacketData::KeyFlags(_)))
+            })
+        })
+        .max_by_key(|signature| signature.created().copied())
+        .is_none_or(|signature| signature.key_flags().sign())
+}
+
+fn issued_by(signature: &Signature, key: &impl KeyDetails) -> bool {
+    signature.issuer().contains(&&key.key_id())
+        || signature.issuer_fingerprint().contains(&&key.fingerprint())
+}
+
+/// Checks `signature` with `signing_key`, which belongs to `key`.
+fn check(
+    key: &SignedPublicKey,
+    signing_key: &impl PublicKeyTrait,
+    signature: &Signature,
+    message: &[u8],
+) -> Result<(), SignatureError> {
+    let revoked = key
+        .details
+        .revocation_signatures
+        .iter()
+        .any(|revocation| revocation.verify_key(&key.primary_key).is_ok());
+    let expired = match (key.expires_at(), signature.created()) {
+        (Some(expires_at), Some(created)) => *created > expires_at,
+        _ => false,
+    };
+    if revoked || expired {
+        return Err(SignatureError::UnusableKey {
+            fingerprint: fingerprint(key),
+        });
+    }
+    signature
+        .verify(signing_key, message)
+        .map_err(|_mismatch| SignatureError::Invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The certificate and the single signature over a message in the example files.
+    fn load(key: &str, signature: &str) -> (SignedPublicKey, Signature) {
+        let key = std::fs::read(key).expect("the key is readable");
+        let signature = std::fs::read(signature).expect("the signature is readable");
+        let mut keys = parse_keys(&key).expect("Expected the key to parse.");
+        let (mut signatures, _) =
+            parse_signatures(&signature).expect("Expected the signature to parse.");
+        (keys.remove(0), signatures.remove(0))
+    }
+
+    #[test]
+    fn test_signing_subkey() {
+        let (key, signature) = load(
+            "examples/keys/release.openpgp.asc",
+            "examples/signatures/openpgp.asc",
+        );
+        let message = std::fs::read("examples/SHA256SUMS").expect("SHA256SUMS is readable");
+        assert!(matches!(verify(&key, &signature, &message), Some(Ok(()))));
+        assert!(matches!(
+            verify(&key, &signature, b"tampered"),
+            Some(Err(SignatureError::Invalid))
+        ));
+
+        // Without its binding signature, the subkey isn't part of the certificate.
+        let mut unbound = key.clone();
+        for subkey in &mut unbound.public_subkeys {
+            subkey.signatures.clear();
+        }
+        assert!(matches!(
+            verify(&unbound, &signature, &message),
+            Some(Err(SignatureError::UnusableKey { fingerprint }))
+                if fingerprint == "AE8C91B7DAD226BE2FD19FEE9338E23FE65BC9C9"
+        ));
+
+        // Other certificates don't claim the signature at all.
+        let (other, _) = load(
+            "examples/openpgp/revoked-subkey.asc",
+            "examples/openpgp/revoked-subkey.sig",
+        );
+        assert!(verify(&other, &signature, &message).is_none());
+    }
+
+    #[test]
+    fn test_unusable_subkeys() {
+        let message = std::fs::read("examples/valid.txt").expect("valid.txt is readable");
+        // Made a day after the subkey expired, and a day before the subkey was revoked.
+        for (key, signature) in [
+            (
+                "examples/openpgp/expired-subkey.asc",
+                "examples/openpgp/expired-subkey.sig",
+            ),
+            (
+                "examples/openpgp/revoked-subkey.asc",
+                "examples/openpgp/revoked-subkey.sig",
+            ),
+        ] {
+            let (key, signature) = load(key, signature);
+            assert!(
+                matches!(
+                    verify(&key, &signature, &message),
+                    Some(Err(SignatureError::UnusableKey { .. }))
+                ),
+                "{key:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_certify_only_primary_key() {
+        let message = std::fs::read("examples/valid.txt").expect("valid.txt is readable");
+        // Made by the primary key the day before its usage was changed to certification only.
+        let (key, signature) = load(
+            "examples/openpgp/certify-only.asc",
+            "examples/openpgp/certify-only.sig",
+        );
+        assert!(matches!(
+            verify(&key, &signature, &message),
+            Some(Err(SignatureError::UnusableKey { fingerprint }))
+                if fingerprint == "1F1985227B6F762F6708337E31B6018C190690EC"
+        ));
+    }
+}
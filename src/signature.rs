@@ -0,0 +1,1132 @@
+//! Signatures over checksum files, made with minisign, OpenBSD signify, `ssh-keygen -Y sign`
+//! or OpenPGP
+
+use crate::HashError;
+use data_encoding::{BASE64, BASE64_NOPAD};
+use ed25519_dalek::{Signature as Ed25519Signature, VerifyingKey};
+use pgp::composed::SignedPublicKey;
+use sha2::Digest as _;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// The namespace `ssh-keygen -Y sign -n` must be given for a signature to be accepted.
+///
+/// SSH signatures name what they were made for so that, say, a signed git commit can't be
+/// passed off as a signed file.
+pub const SSH_NAMESPACE: &str = "file";
+
+/// The extensions tried, in order, by [`find_signature`].
+const SIGNATURE_EXTENSIONS: [&str; 4] = ["minisig", "sig", "asc", "gpg"];
+
+/// The extensions of files in the trusted key store, stripped to give the key's name.
+const KEY_EXTENSIONS: [&str; 3] = ["pub", "asc", "gpg"];
+
+/// The tool a signature was made with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SignatureFormat {
+    Minisign,
+    Signify,
+    Ssh,
+    OpenPgp,
+}
+
+impl SignatureFormat {
+    /// The human-readable name of the format, e.g. `minisign`.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Minisign => "minisign",
+            Self::Signify => "signify",
+            Self::Ssh => "SSH",
+            Self::OpenPgp => "OpenPGP",
+        }
+    }
+}
+
+impl fmt::Display for SignatureFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A public key, as written by minisign, signify, `ssh-keygen` or `gpg --export`.
+///
+/// minisign and signify keys are interchangeable: both carry an 8-byte key ID that signatures
+/// refer to. SSH keys are matched by the key itself, which every SSH signature embeds.
+/// OpenPGP keys are whole certificates, whose subkeys may sign on behalf of the primary key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicKey {
+    kind: KeyKind,
+    comment: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[expect(
+    clippy::large_enum_variant,
+    reason = "there are only ever a handful of keys"
+)]
+enum KeyKind {
+    /// A minisign, signify or SSH key. Only SSH keys have no key ID.
+    Ed25519 {
+        key_id: Option<[u8; 8]>,
+        key: VerifyingKey,
+    },
+
+    /// An OpenPGP certificate: a primary key, its subkeys and the signatures binding them.
+    OpenPgp(Box<SignedPublicKey>),
+}
+
+impl PublicKey {
+    /// Parses every key in the text of a public key file.
+    ///
+    /// That is a single minisign or signify key, any number of OpenSSH public keys, one per
+    /// line, optionally preceded by principals as in an `allowed_signers` file, or any number of
+    /// ASCII-armored OpenPGP keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text is not a key file, or any minisign, signify or SSH key in it
+    /// is not Ed25519.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use hash_checker::PublicKey;
+    /// let text = std::fs::read_to_string("examples/keys/release.ssh.pub").expect("readable");
+    /// let keys = PublicKey::parse_all(&text).expect("valid key");
+    /// assert_eq!(keys[0].comment(), "release@example.com");
+    /// ```
+    pub fn parse_all(text: &str) -> Result<Vec<Self>, SignatureError> {
+        if crate::openpgp::is_armored(text.as_bytes()) {
+            return Self::parse_bytes(text.as_bytes());
+        }
+        if text.starts_with(UNTRUSTED_COMMENT) {
+            let mut lines = text.lines();
+            let comment = lines
+                .next()
+                .and_then(|line| line.strip_prefix(UNTRUSTED_COMMENT))
+                .unwrap_or_default();
+            let blob = decode_line(lines.next(), "public key")?;
+            let (algorithm, rest) = blob.split_at_checked(2).unwrap_or_default();
+            if algorithm != b"Ed" {
+                return Err(SignatureError::UnsupportedAlgorithm(lossy(algorithm)));
+            }
+            let (key_id, key) = split_key_id::<32>(rest, "public key")?;
+            return Ok(vec![Self {
+                kind: KeyKind::Ed25519 {
+                    key_id: Some(key_id),
+                    key: verifying_key(&key)?,
+                },
+                comment: comment.trim().to_owned(),
+            }]);
+        }
+        let keys: Vec<Self> = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(parse_ssh_line)
+            .collect::<Result<_, _>>()?;
+        if keys.is_empty() {
+            return Err(SignatureError::Malformed("no public key found"));
+        }
+        Ok(keys)
+    }
+
+    /// Like [`PublicKey::parse_all`], but also reads binary OpenPGP keys, such as the
+    /// `.gpg` keyrings Debian and Ubuntu publish.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the contents are not a key file.
+    pub fn parse_bytes(contents: &[u8]) -> Result<Vec<Self>, SignatureError> {
+        if !crate::openpgp::is_openpgp(contents) {
+            return Self::parse_all(&String::from_utf8_lossy(contents));
+        }
+        Ok(crate::openpgp::parse_keys(contents)?
+            .into_iter()
+            .map(|key| Self {
+                comment: crate::openpgp::user_id(&key),
+                kind: KeyKind::OpenPgp(Box::new(key)),
+            })
+            .collect())
+    }
+
+    /// The comment stored alongside the key, typically naming its owner. For OpenPGP keys this
+    /// is the user ID, e.g. `Jane Doe <jane@example.com>`.
+    #[must_use]
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    /// How the key is usually identified: its key ID as printed by minisign, its SHA-256
+    /// fingerprint as printed by `ssh-keygen -l`, or its OpenPGP fingerprint.
+    #[must_use]
+    pub fn fingerprint(&self) -> String {
+        match &self.kind {
+            KeyKind::Ed25519 {
+                key_id: Some(key_id),
+                ..
+            } => format!("{:016X}", u64::from_le_bytes(*key_id)),
+            KeyKind::Ed25519 { key, .. } => format!(
+                "SHA256:{}",
+                BASE64_NOPAD.encode(&sha2::Sha256::digest(ssh_key_blob(key)))
+            ),
+            KeyKind::OpenPgp(key) => crate::openpgp::fingerprint(key),
+        }
+    }
+}
+
+/// The first line of minisign and signify files.
+const UNTRUSTED_COMMENT: &str = "untrusted comment:";
+
+/// The line introducing the signed comment in minisign signatures.
+const TRUSTED_COMMENT: &str = "trusted comment: ";
+
+/// The key type of Ed25519 keys and signatures in the SSH wire format.
+const SSH_ED25519: &str = "ssh-ed25519";
+
+/// A signature, as written by minisign, signify, `ssh-keygen -Y sign` or `gpg`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    format: SignatureFormat,
+    kind: SignatureKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum SignatureKind {
+    /// A minisign or signify signature, naming the key it was made with by ID.
+    KeyId {
+        key_id: [u8; 8],
+        /// Whether the BLAKE2b-512 hash of the file was signed rather than the file itself,
+        /// as minisign does by default.
+        prehashed: bool,
+        signature: Ed25519Signature,
+        /// minisign's trusted comment and the signature over it and `signature`.
+        trusted_comment: Option<(String, Ed25519Signature)>,
+    },
+
+    /// An `SSHSIG` signature, carrying the key it was made with.
+    Ssh {
+        key: VerifyingKey,
+        namespace: String,
+        hash_algorithm: String,
+        signed_data: Vec<u8>,
+        signature: Ed25519Signature,
+    },
+
+    /// One or more OpenPGP signatures, either detached or from a clearsigned message.
+    OpenPgp {
+        signatures: Vec<pgp::packet::Signature>,
+        /// The text a clearsigned message carries, which is what was signed.
+        signed_text: Option<String>,
+    },
+}
+
+impl Signature {
+    /// Parses the text of a signature file, working out which tool made it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text is not a signature, or a minisign, signify or SSH signature
+    /// that is not Ed25519.
+    pub fn parse(text: &str) -> Result<Self, SignatureError> {
+        if text.trim_start().starts_with(SSH_ARMOR_BEGIN) {
+            return parse_ssh_signature(text);
+        }
+        if crate::openpgp::is_armored(text.as_bytes()) {
+            return Self::parse_bytes(text.as_bytes());
+        }
+        if !text.starts_with(UNTRUSTED_COMMENT) {
+            return Err(SignatureError::Malformed(
+                "not a minisign, signify, SSH or OpenPGP signature",
+            ));
+        }
+        let mut lines = text.lines().skip(1);
+        let blob = decode_line(lines.next(), "signature")?;
+        let (algorithm, rest) = blob.split_at_checked(2).unwrap_or_default();
+        let prehashed = match algorithm {
+            b"Ed" => false,
+            b"ED" => true,
+            _ => return Err(SignatureError::UnsupportedAlgorithm(lossy(algorithm))),
+        };
+        let (key_id, signature) = split_key_id::<64>(rest, "signature")?;
+        let signature = Ed25519Signature::from_bytes(&signature);
+
+        // Only minisign follows the signature with a trusted comment.
+        let Some(line) = lines.next() else {
+            return Ok(Self {
+                format: SignatureFormat::Signify,
+                kind: SignatureKind::KeyId {
+                    key_id,
+                    prehashed,
+                    signature,
+                    trusted_comment: None,
+                },
+            });
+        };
+        let trusted_comment = line
+            .strip_prefix(TRUSTED_COMMENT)
+            .ok_or(SignatureError::Malformed("expected a trusted comment"))?;
+        let global_signature = decode_line(lines.next(), "signature")?;
+        let global_signature = Ed25519Signature::from_slice(&global_signature)
+            .map_err(|_invalid| SignatureError::Malformed("truncated signature"))?;
+        Ok(Self {
+            format: SignatureFormat::Minisign,
+            kind: SignatureKind::KeyId {
+                key_id,
+                prehashed,
+                signature,
+                trusted_comment: Some((trusted_comment.to_owned(), global_signature)),
+            },
+        })
+    }
+
+    /// Reads and parses the signature file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a signature.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SignatureError> {
+        let contents = crate::hashing::read_file(path.as_ref())?;
+        Self::parse_bytes(&contents)
+    }
+
+    /// Like [`Signature::parse`], but also reads binary OpenPGP signatures, as written by
+    /// `gpg --detach-sign`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the contents are not a signature.
+    pub fn parse_bytes(contents: &[u8]) -> Result<Self, SignatureError> {
+        if !crate::openpgp::is_openpgp(contents) {
+            return Self::parse(&String::from_utf8_lossy(contents));
+        }
+        let (signatures, signed_text) = crate::openpgp::parse_signatures(contents)?;
+        Ok(Self {
+            format: SignatureFormat::OpenPgp,
+            kind: SignatureKind::OpenPgp {
+                signatures,
+                signed_text,
+            },
+        })
+    }
+
+    /// The tool the signature was made with.
+    #[must_use]
+    pub fn format(&self) -> SignatureFormat {
+        self.format
+    }
+
+    /// The text of a clearsigned OpenPGP message, which the signature is over. `None` for
+    /// detached signatures.
+    #[must_use]
+    pub fn signed_text(&self) -> Option<&str> {
+        match &self.kind {
+            SignatureKind::OpenPgp { signed_text, .. } => signed_text.as_deref(),
+            SignatureKind::KeyId { .. } | SignatureKind::Ssh { .. } => None,
+        }
+    }
+}
+
+/// Who made a signature that checked out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signer {
+    pub format: SignatureFormat,
+
+    /// The name the key was trusted under.
+    pub name: String,
+
+    /// The comment stored with the key, typically naming its owner. For OpenPGP keys this is
+    /// the user ID.
+    pub comment: String,
+
+    /// See [`PublicKey::fingerprint`].
+    pub fingerprint: String,
+
+    /// minisign's trusted comment, which is covered by the signature.
+    pub trusted_comment: Option<String>,
+}
+
+impl fmt::Display for Signer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} key {} ({})",
+            self.format, self.name, self.fingerprint
+        )
+    }
+}
+
+/// A key trusted to sign checksum files, and the name it was trusted under.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrustedKey {
+    pub name: String,
+    pub key: PublicKey,
+}
+
+/// The public keys whose signatures are accepted.
+///
+/// On disk the store is a directory holding one public key file per signer, named after them.
+/// Files can be copied in by hand or added with [`TrustedKeys::install`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TrustedKeys {
+    keys: Vec<TrustedKey>,
+}
+
+impl TrustedKeys {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The directory the keys are kept in unless told otherwise, next to the app's saved state.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[must_use]
+    pub fn default_dir() -> Option<PathBuf> {
+        eframe::storage_dir("Hash Checker").map(|dir| dir.join("trusted-keys"))
+    }
+
+    /// Loads every key file in `dir`. A missing directory is an empty store.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory or any file in it can't be read, or a file is not a
+    /// public key.
+    pub fn load(dir: &Path) -> Result<Self, SignatureError> {
+        let mut keys = Self::new();
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(keys),
+            Err(err) => return Err(HashError::open(dir, err).into()),
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()
+            .map_err(|err| HashError::open(dir, err))?;
+        paths.sort();
+        for path in paths {
+            if path.is_file() {
+                keys.add_file(&path)?;
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Trusts every key in the file at `path`, named after the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or is not a public key.
+    pub fn add_file(&mut self, path: &Path) -> Result<(), SignatureError> {
+        let contents = crate::hashing::read_file(path)?;
+        let keys = PublicKey::parse_bytes(&contents)?;
+        let name = key_name(path);
+        for key in keys {
+            self.add(name.clone(), key);
+        }
+        Ok(())
+    }
+
+    /// Trusts `key` under `name`.
+    pub fn add(&mut self, name: impl Into<String>, key: PublicKey) {
+        self.keys.push(TrustedKey {
+            name: name.into(),
+            key,
+        });
+    }
+
+    /// Copies the key file at `key_file` into the store at `dir` as `name`, creating the
+    /// directory if needed. Returns where the key was saved.
+    ///
+    /// `.asc` and `.gpg` files keep their extension; anything else is saved as `name.pub`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file is not a public key, `name` is not a plain file name or is
+    /// already taken, or the key can't be saved.
+    pub fn install(dir: &Path, name: &str, key_file: &Path) -> Result<PathBuf, SignatureError> {
+        let contents = crate::hashing::read_file(key_file)?;
+        PublicKey::parse_bytes(&contents)?;
+        let name = valid_key_name(name)?;
+        if key_paths(dir, name).any(|path| path.exists()) {
+            return Err(SignatureError::KeyExists(name.to_owned()));
+        }
+        let extension = key_file
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .filter(|extension| KEY_EXTENSIONS.contains(extension))
+            .unwrap_or("pub");
+        let path = dir.join(format!("{name}.{extension}"));
+        std::fs::create_dir_all(dir)
+            .and_then(|()| std::fs::write(&path, contents))
+            .map_err(|err| HashError::open(&path, err))?;
+        Ok(path)
+    }
+
+    /// Deletes the key called `name` from the store at `dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no such key or it can't be deleted.
+    pub fn remove(dir: &Path, name: &str) -> Result<(), SignatureError> {
+        let name = valid_key_name(name)?;
+        for path in key_paths(dir, name) {
+            if path.is_file() {
+                return std::fs::remove_file(&path)
+                    .map_err(|err| HashError::open(&path, err).into());
+            }
+        }
+        Err(SignatureError::UnknownKey(name.to_owned()))
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &TrustedKey> {
+        self.keys.iter()
+    }
+
+    /// Checks that `signature` over `message` was made by one of the trusted keys.
+    ///
+    /// A clearsigned OpenPGP message carries its own text, so `message` must be that same
+    /// clearsigned message; see [`Signature::signed_text`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SignatureError::UntrustedKey`] if none of the keys made the signature,
+    /// [`SignatureError::Invalid`] if one did but `message` has changed since,
+    /// [`SignatureError::WrongNamespace`] for SSH signatures not made for files,
+    /// [`SignatureError::UnusableKey`] for OpenPGP keys that have been revoked or expired, and
+    /// [`SignatureError::Malformed`] if a clearsigned message is checked against anything but
+    /// itself.
+    pub fn verify(&self, message: &[u8], signature: &Signature) -> Result<Signer, SignatureError> {
+        let (trusted, trusted_comment) = match &signature.kind {
+            SignatureKind::KeyId {
+                key_id,
+                prehashed,
+                signature: file_signature,
+                trusted_comment,
+            } => {
+                let (trusted, key) = self
+                    .ed25519_keys()
+                    .find(|(_, id, _)| *id == Some(*key_id))
+                    .map(|(trusted, _, key)| (trusted, key))
+                    .ok_or_else(|| SignatureError::UntrustedKey {
+                        fingerprint: format!("{:016X}", u64::from_le_bytes(*key_id)),
+                    })?;
+                let verified = if *prehashed {
+                    key.verify_strict(&blake2::Blake2b512::digest(message), file_signature)
+                } else {
+                    key.verify_strict(message, file_signature)
+                };
+                verified.map_err(|_mismatch| SignatureError::Invalid)?;
+                if let Some((comment, global_signature)) = trusted_comment {
+                    let mut signed = file_signature.to_bytes().to_vec();
+                    signed.extend_from_slice(comment.as_bytes());
+                    key.verify_strict(&signed, global_signature)
+                        .map_err(|_mismatch| SignatureError::Invalid)?;
+                }
+                (
+                    trusted,
+                    trusted_comment.as_ref().map(|(comment, _)| comment),
+                )
+            }
+            SignatureKind::Ssh {
+                key,
+                namespace,
+                hash_algorithm,
+                signed_data,
+                signature: ssh_signature,
+            } => {
+                if namespace != SSH_NAMESPACE {
+                    return Err(SignatureError::WrongNamespace(namespace.clone()));
+                }
+                let trusted = self
+                    .ed25519_keys()
+                    .find(|(_, id, trusted_key)| id.is_none() && *trusted_key == key)
+                    .map(|(trusted, _, _)| trusted)
+                    .ok_or_else(|| SignatureError::UntrustedKey {
+                        fingerprint: PublicKey {
+                            kind: KeyKind::Ed25519 {
+                                key_id: None,
+                                key: *key,
+                            },
+                            comment: String::new(),
+                        }
+                        .fingerprint(),
+                    })?;
+                let mut signed = signed_data.clone();
+                let hash = match hash_algorithm.as_str() {
+                    "sha256" => sha2::Sha256::digest(message).to_vec(),
+                    "sha512" => sha2::Sha512::digest(message).to_vec(),
+                    _ => return Err(SignatureError::UnsupportedAlgorithm(hash_algorithm.clone())),
+                };
+                put_ssh_string(&mut signed, &hash);
+                key.verify_strict(&signed, ssh_signature)
+                    .map_err(|_mismatch| SignatureError::Invalid)?;
+                (trusted, None)
+            }
+            SignatureKind::OpenPgp {
+                signatures,
+                signed_text,
+            } => {
+                // A clearsigned message vouches for its own text, so it may only be checked
+                // against itself. As the "detached signature" of another file it would vouch
+                // for that file whatever it says.
+                if let Some(signed_text) = signed_text {
+                    let own_text = std::str::from_utf8(message)
+                        .ok()
+                        .and_then(crate::openpgp::signed_text)
+                        .map(|(_, text)| text);
+                    if own_text.as_deref() != Some(signed_text.as_str()) {
+                        return Err(SignatureError::Malformed(
+                            "a clearsigned message only signs itself, not a separate file",
+                        ));
+                    }
+                }
+                let message = signed_text.as_deref().map_or(message, str::as_bytes);
+                (self.verify_openpgp(message, signatures)?, None)
+            }
+        };
+        Ok(Signer {
+            format: signature.format,
+            name: trusted.name.clone(),
+            comment: trusted.key.comment.clone(),
+            fingerprint: trusted.key.fingerprint(),
+            trusted_comment: trusted_comment.cloned(),
+        })
+    }
+
+    /// Every trusted minisign, signify and SSH key, with its key ID if it has one.
+    fn ed25519_keys(&self) -> impl Iterator<Item = (&TrustedKey, Option<[u8; 8]>, &VerifyingKey)> {
+        self.keys
+            .iter()
+            .filter_map(|trusted| match &trusted.key.kind {
+                KeyKind::Ed25519 { key_id, key } => Some((trusted, *key_id, key)),
+                KeyKind::OpenPgp(_) => None,
+            })
+    }
+
+    /// Finds the trusted OpenPGP key that made one of `signatures` over `message`.
+    ///
+    /// Files are often signed by several keys, such as one per release manager, so any one
+    /// valid signature from a trusted key is enough. A bad one from a trusted key is not,
+    /// unless another trusted key vouches for the same message.
+    fn verify_openpgp(
+        &self,
+        message: &[u8],
+        signatures: &[pgp::packet::Signature],
+    ) -> Result<&TrustedKey, SignatureError> {
+        let mut error = None;
+        for signature in signatures {
+            let mut checked = false;
+            for trusted in &self.keys {
+                let KeyKind::OpenPgp(key) = &trusted.key.kind else {
+                    continue;
+                };
+                match crate::openpgp::verify(key, signature, message) {
+                    Some(Ok(())) => return Ok(trusted),
+                    Some(Err(err)) => {
+                        checked = true;
+                        error = Some(err);
+                    }
+                    None => {}
+                }
+            }
+            if !checked && error.is_none() {
+                error = Some(SignatureError::UntrustedKey {
+                    fingerprint: crate::openpgp::issuer(signature),
+                });
+            }
+        }
+        Err(error.unwrap_or(SignatureError::Malformed("no OpenPGP signature found")))
+    }
+}
+
+/// Looks for a detached signature next to the file at `path`, such as `SHA256SUMS.minisig`,
+/// `SHA256SUMS.sig`, `SHA256SUMS.asc` or `SHA256SUMS.gpg`.
+#[must_use]
+pub fn find_signature(path: &Path) -> Option<PathBuf> {
+    SIGNATURE_EXTENSIONS
+        .into_iter()
+        .map(|extension| {
+            let mut name = path.as_os_str().to_owned();
+            name.push(".");
+            name.push(extension);
+            PathBuf::from(name)
+        })
+        .find(|candidate| candidate.is_file())
+}
+
+/// An error that occurred while reading or checking a signature or key.
+#[derive(Debug)]
+pub enum SignatureError {
+    /// A signature or key file could not be read.
+    Read(HashError),
+
+    /// No signature was found next to the signed file.
+    NotFound { path: PathBuf },
+
+    /// The text is not a signature or key in any of the understood formats.
+    Malformed(&'static str),
+
+    /// The minisign, signify or SSH signature or key uses something other than Ed25519, or an
+    /// unknown hash.
+    UnsupportedAlgorithm(String),
+
+    /// The SSH signature was made for something other than files, such as git commits.
+    WrongNamespace(String),
+
+    /// None of the trusted keys made the signature.
+    UntrustedKey { fingerprint: String },
+
+    /// A trusted OpenPGP key made the signature, but had been revoked or had expired by then,
+    /// or it was made by a subkey that isn't bound to the key for signing.
+    UnusableKey { fingerprint: String },
+
+    /// A trusted key made the signature, but not over this data: the file or the signature
+    /// has been tampered with.
+    Invalid,
+
+    /// A key name given for the store is empty or contains path separators.
+    InvalidKeyName(String),
+
+    /// The store already has a key by this name.
+    KeyExists(String),
+
+    /// The store has no key by this name.
+    UnknownKey(String),
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(err) => err.fmt(f),
+            Self::NotFound { path } => write!(f, "no signature found for {}", path.display()),
+            Self::Malformed(reason) => write!(f, "malformed signature or key: {reason}"),
+            Self::UnsupportedAlgorithm(algorithm) => {
+                write!(f, "unsupported signature algorithm {algorithm:?}")
+            }
+            Self::WrongNamespace(namespace) => write!(
+                f,
+                "SSH signature is for {namespace:?}, not {SSH_NAMESPACE:?} (sign with `ssh-keygen -Y sign -n {SSH_NAMESPACE}`)"
+            ),
+            Self::UntrustedKey { fingerprint } => {
+                write!(f, "signed by untrusted key {fingerprint}")
+            }
+            Self::UnusableKey { fingerprint } => {
+                write!(
+                    f,
+                    "key {fingerprint} is revoked, expired or not valid for signing"
+                )
+            }
+            Self::Invalid => f.write_str("BAD signature: the file or signature has been modified"),
+            Self::InvalidKeyName(name) => write!(f, "invalid key name {name:?}"),
+            Self::KeyExists(name) => write!(f, "a key named {name:?} is already trusted"),
+            Self::UnknownKey(name) => write!(f, "no trusted key named {name:?}"),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Read(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<HashError> for SignatureError {
+    fn from(err: HashError) -> Self {
+        Self::Read(err)
+    }
+}
+
+/// Decodes a Base64 line of a minisign or signify file.
+fn decode_line(line: Option<&str>, what: &'static str) -> Result<Vec<u8>, SignatureError> {
+    let line = line.ok_or(SignatureError::Malformed(what))?;
+    BASE64
+        .decode(line.trim().as_bytes())
+        .map_err(|_invalid| SignatureError::Malformed(what))
+}
+
+/// Splits `<key ID><N bytes>`, what follows the algorithm in minisign and signify files.
+fn split_key_id<const N: usize>(
+    blob: &[u8],
+    what: &'static str,
+) -> Result<([u8; 8], [u8; N]), SignatureError> {
+    if blob.len() != 8 + N {
+        return Err(SignatureError::Malformed(what));
+    }
+    let (key_id, rest) = blob.split_at(8);
+    Ok((
+        key_id
+            .try_into()
+            .map_err(|_invalid| SignatureError::Malformed(what))?,
+        rest.try_into()
+            .map_err(|_invalid| SignatureError::Malformed(what))?,
+    ))
+}
+
+fn verifying_key(bytes: &[u8; 32]) -> Result<VerifyingKey, SignatureError> {
+    VerifyingKey::from_bytes(bytes)
+        .map_err(|_invalid| SignatureError::Malformed("invalid Ed25519 key"))
+}
+
+fn lossy(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Parses `[principals] ssh-ed25519 <base64> [comment]`.
+fn parse_ssh_line(line: &str) -> Result<PublicKey, SignatureError> {
+    let mut words = line.split_whitespace();
+    let mut principals = Vec::new();
+    let key_type = loop {
+        let word = words
+            .next()
+            .ok_or(SignatureError::Malformed("expected an SSH public key"))?;
+        if word.starts_with("ssh-") || word.starts_with("ecdsa-") || word.starts_with("sk-") {
+            break word;
+        }
+        principals.push(word);
+    };
+    if key_type != SSH_ED25519 {
+        return Err(SignatureError::UnsupportedAlgorithm(key_type.to_owned()));
+    }
+    let blob = words
+        .next()
+        .and_then(|blob| BASE64.decode(blob.as_bytes()).ok())
+        .ok_or(SignatureError::Malformed("expected an SSH public key"))?;
+    let comment = if principals.is_empty() {
+        words.collect::<Vec<_>>().join(" ")
+    } else {
+        principals.join(" ")
+    };
+    Ok(PublicKey {
+        kind: KeyKind::Ed25519 {
+            key_id: None,
+            key: parse_ssh_key(&mut SshReader(&blob))?,
+        },
+        comment,
+    })
+}
+
+const SSH_ARMOR_BEGIN: &str = "-----BEGIN SSH SIGNATURE-----";
+const SSH_ARMOR_END: &str = "-----END SSH SIGNATURE-----";
+const SSHSIG_MAGIC: &[u8] = b"SSHSIG";
+
+/// Parses an armored `SSHSIG` signature, as described in OpenSSH's `PROTOCOL.sshsig`.
+fn parse_ssh_signature(text: &str) -> Result<Signature, SignatureError> {
+    let armored: String = text
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| *line != SSH_ARMOR_BEGIN)
+        .skip(1)
+        .take_while(|line| *line != SSH_ARMOR_END)
+        .collect();
+    let blob = BASE64
+        .decode(armored.as_bytes())
+        .map_err(|_invalid| SignatureError::Malformed("invalid Base64 in SSH signature"))?;
+    let mut reader = SshReader(&blob);
+    if reader.take(SSHSIG_MAGIC.len())? != SSHSIG_MAGIC {
+        return Err(SignatureError::Malformed("not an SSH signature"));
+    }
+    if reader.u32()? != 1 {
+        return Err(SignatureError::Malformed(
+            "unsupported SSH signature version",
+        ));
+    }
+    let key = parse_ssh_key(&mut SshReader(reader.string()?))?;
+    let namespace = reader.string()?;
+    let reserved = reader.string()?;
+    let hash_algorithm = reader.string()?;
+    let mut signature = SshReader(reader.string()?);
+    let signature_type = signature.string()?;
+    if signature_type != SSH_ED25519.as_bytes() {
+        return Err(SignatureError::UnsupportedAlgorithm(lossy(signature_type)));
+    }
+    let signature = Ed25519Signature::from_slice(signature.string()?)
+        .map_err(|_invalid| SignatureError::Malformed("truncated SSH signature"))?;
+
+    // Everything that is signed except the hash of the message itself.
+    let mut signed_data = SSHSIG_MAGIC.to_vec();
+    put_ssh_string(&mut signed_data, namespace);
+    put_ssh_string(&mut signed_data, reserved);
+    put_ssh_string(&mut signed_data, hash_algorithm);
+    Ok(Signature {
+        format: SignatureFormat::Ssh,
+        kind: SignatureKind::Ssh {
+            key,
+            namespace: lossy(namespace),
+            hash_algorithm: lossy(hash_algorithm),
+            signed_data,
+            signature,
+        },
+    })
+}
+
+/// Parses the wire encoding of an Ed25519 SSH public key.
+fn parse_ssh_key(reader: &mut SshReader<'_>) -> Result<VerifyingKey, SignatureError> {
+    let key_type = reader.string()?;
+    if key_type != SSH_ED25519.as_bytes() {
+        return Err(SignatureError::UnsupportedAlgorithm(lossy(key_type)));
+    }
+    let key: &[u8; 32] = reader
+        .string()?
+        .try_into()
+        .map_err(|_invalid| SignatureError::Malformed("invalid Ed25519 key"))?;
+    verifying_key(key)
+}
+
+/// The wire encoding of an Ed25519 SSH public key, which its fingerprint is a hash of.
+fn ssh_key_blob(key: &VerifyingKey) -> Vec<u8> {
+    let mut blob = Vec::new();
+    put_ssh_string(&mut blob, SSH_ED25519.as_bytes());
+    put_ssh_string(&mut blob, key.as_bytes());
+    blob
+}
+
+/// Appends `bytes` with the 32-bit big-endian length prefix SSH uses for strings.
+fn put_ssh_string(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    let len = u32::try_from(bytes.len()).expect("SSH strings are shorter than 4 GiB");
+    buffer.extend_from_slice(&len.to_be_bytes());
+    buffer.extend_from_slice(bytes);
+}
+
+/// Reads values in the SSH wire format.
+struct SshReader<'a>(&'a [u8]);
+
+impl<'a> SshReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SignatureError> {
+        let (taken, rest) = self
+            .0
+            .split_at_checked(len)
+            .ok_or(SignatureError::Malformed("truncated SSH data"))?;
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> Result<u32, SignatureError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes(
+            bytes.try_into().expect("took exactly four bytes"),
+        ))
+    }
+
+    fn string(&mut self) -> Result<&'a [u8], SignatureError> {
+        let len = self.u32()?;
+        self.take(usize::try_from(len).unwrap_or(usize::MAX))
+    }
+}
+
+/// The name a key file is trusted under: its file name without the `.pub`, `.asc` or `.gpg`
+/// extension.
+pub(crate) fn key_name(path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    strip_key_extension(&name).to_owned()
+}
+
+fn strip_key_extension(name: &str) -> &str {
+    KEY_EXTENSIONS
+        .into_iter()
+        .find_map(|extension| name.strip_suffix(extension)?.strip_suffix('.'))
+        .unwrap_or(name)
+}
+
+/// Every path the key called `name` could be stored at in `dir`.
+fn key_paths<'a>(dir: &'a Path, name: &'a str) -> impl Iterator<Item = PathBuf> + 'a {
+    KEY_EXTENSIONS
+        .into_iter()
+        .map(move |extension| dir.join(format!("{name}.{extension}")))
+        .chain(std::iter::once(dir.join(name)))
+}
+
+fn valid_key_name(name: &str) -> Result<&str, SignatureError> {
+    let name = strip_key_extension(name);
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(SignatureError::InvalidKeyName(name.to_owned()));
+    }
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trusted_keys() -> TrustedKeys {
+        TrustedKeys::load(Path::new("examples/keys")).expect("Expected the example keys to load.")
+    }
+
+    fn message() -> Vec<u8> {
+        std::fs::read("examples/SHA256SUMS").expect("SHA256SUMS is readable")
+    }
+
+    #[test]
+    fn test_verify_each_format() {
+        let keys = trusted_keys();
+        let cases = [
+            (
+                "examples/SHA256SUMS.minisig",
+                SignatureFormat::Minisign,
+                "release.minisign",
+            ),
+            (
+                "examples/signatures/legacy.minisig",
+                SignatureFormat::Minisign,
+                "release.minisign",
+            ),
+            (
+                "examples/signatures/signify.sig",
+                SignatureFormat::Signify,
+                "release.signify",
+            ),
+            (
+                "examples/signatures/ssh.sig",
+                SignatureFormat::Ssh,
+                "release.ssh",
+            ),
+            (
+                "examples/signatures/openpgp.asc",
+                SignatureFormat::OpenPgp,
+                "release.openpgp",
+            ),
+            (
+                "examples/signatures/openpgp.gpg",
+                SignatureFormat::OpenPgp,
+                "release.openpgp",
+            ),
+        ];
+        for (path, format, name) in cases {
+            let signature = Signature::read(path).expect("Expected the signature to parse.");
+            assert_eq!(signature.format(), format, "{path}");
+            let signer = keys
+                .verify(&message(), &signature)
+                .unwrap_or_else(|err| panic!("{path}: {err}"));
+            assert_eq!(signer.name, name, "{path}");
+        }
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let keys = trusted_keys();
+        let mut tampered = message();
+        tampered[0] ^= 1;
+        for path in [
+            "examples/SHA256SUMS.minisig",
+            "examples/signatures/signify.sig",
+            "examples/signatures/ssh.sig",
+            "examples/signatures/openpgp.gpg",
+        ] {
+            let signature = Signature::read(path).expect("Expected the signature to parse.");
+            assert!(
+                matches!(
+                    keys.verify(&tampered, &signature),
+                    Err(SignatureError::Invalid)
+                ),
+                "{path}"
+            );
+        }
+
+        // The trusted comment is signed too.
+        let text = std::fs::read_to_string("examples/SHA256SUMS.minisig").expect("readable");
+        let signature = Signature::parse(&text.replace("timestamp:", "timestamp:1"))
+            .expect("Expected the signature to parse.");
+        assert!(matches!(
+            keys.verify(&message(), &signature),
+            Err(SignatureError::Invalid)
+        ));
+    }
+
+    #[test]
+    fn test_clearsigned() {
+        let keys = trusted_keys();
+        let text = std::fs::read_to_string("examples/CHECKSUM").expect("CHECKSUM is readable");
+        let signature = Signature::parse(&text).expect("Expected the message to parse.");
+        assert!(
+            signature
+                .signed_text()
+                .is_some_and(|signed| signed.starts_with("SHA256 (valid.txt) = "))
+        );
+        let signer = keys
+            .verify(text.as_bytes(), &signature)
+            .expect("Expected the signature to verify.");
+        assert_eq!(
+            signer.comment,
+            "Example Release Signing <release@example.com>"
+        );
+        // Signed by the subkey, but reported as the certificate.
+        assert_eq!(
+            signer.fingerprint,
+            "D1860C49FBA57801B879C7CB681A853DD93A4DBD"
+        );
+
+        let tampered_text = text.replace("6d78", "6d79");
+        let tampered = Signature::parse(&tampered_text).expect("Expected the message to parse.");
+        assert!(matches!(
+            keys.verify(tampered_text.as_bytes(), &tampered),
+            Err(SignatureError::Invalid)
+        ));
+
+        // Used as the detached signature of another file, it must not vouch for that file.
+        let forged = format!("{}  valid.txt\n", "0".repeat(64));
+        assert!(matches!(
+            keys.verify(forged.as_bytes(), &signature),
+            Err(SignatureError::Malformed(_))
+        ));
+        assert!(matches!(
+            keys.verify(tampered_text.as_bytes(), &signature),
+            Err(SignatureError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_untrusted_key() {
+        let signature = Signature::read("examples/signatures/ssh.sig")
+            .expect("Expected the signature to parse.");
+        let err = TrustedKeys::new()
+            .verify(&message(), &signature)
+            .expect_err("no keys are trusted");
+        assert!(
+            matches!(err, SignatureError::UntrustedKey { fingerprint } if fingerprint.starts_with("SHA256:"))
+        );
+    }
+
+    #[test]
+    fn test_fingerprints() {
+        let keys = trusted_keys();
+        let fingerprint = |name: &str| {
+            keys.iter()
+                .find(|trusted| trusted.name == name)
+                .map(|trusted| trusted.key.fingerprint())
+        };
+        // As printed by `minisign -G`, `ssh-keygen -l` and `gpg --fingerprint`. signify doesn't
+        // print key IDs.
+        assert_eq!(
+            fingerprint("release.minisign").as_deref(),
+            Some("191B4760ED90BB23")
+        );
+        assert_eq!(
+            fingerprint("release.ssh").as_deref(),
+            Some("SHA256:0ymtIpECpZmcXO+nnAC2a0bgk3NdGrhe9Tg4q6rfS4E")
+        );
+        assert_eq!(
+            fingerprint("release.openpgp").as_deref(),
+            Some("D1860C49FBA57801B879C7CB681A853DD93A4DBD")
+        );
+    }
+
+    #[test]
+    fn test_find_signature() {
+        assert_eq!(
+            find_signature(Path::new("examples/SHA256SUMS")),
+            Some(PathBuf::from("examples/SHA256SUMS.minisig"))
+        );
+        assert_eq!(find_signature(Path::new("examples/valid.txt")), None);
+    }
+}
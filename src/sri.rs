@@ -0,0 +1,283 @@
+//! Subresource Integrity metadata, as in `<script integrity="sha384-…">`
+
+use crate::{Comparison, Digest, HashAlgorithm, HashError};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The algorithms Subresource Integrity allows, weakest first.
+pub const SRI_ALGORITHMS: [HashAlgorithm; 3] = [
+    HashAlgorithm::Sha256,
+    HashAlgorithm::Sha384,
+    HashAlgorithm::Sha512,
+];
+
+/// The Subresource Integrity metadata of a resource: one or more digests, written as the
+/// value of an HTML `integrity` attribute such as `sha384-SOQr… sha512-Eh3d…`.
+///
+/// Parsing follows the W3C specification: tokens with other algorithms or invalid Base64 are
+/// ignored, as are `?options` after a digest. When checking a resource only the strongest
+/// algorithm present counts, and the resource matches if it has any of that algorithm's
+/// digests. This way a weaker digest can't be used to downgrade the check.
+///
+/// # Examples
+///
+/// ```rust
+/// use hash_checker::{HashAlgorithm, Integrity};
+/// let integrity: Integrity =
+///     "sha256-bXg5KliGF3/luG5YWgtpWivNAaBVBLPE44vI7rIegyY= md5-ignored".parse().expect("valid");
+/// assert_eq!(integrity.strongest_algorithm(), HashAlgorithm::Sha256);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Integrity {
+    digests: Vec<Digest>,
+}
+
+impl Integrity {
+    /// Integrity metadata listing every digest in `digests` that Subresource Integrity allows.
+    ///
+    /// Returns `None` if there are none.
+    #[must_use]
+    pub fn from_digests<'a>(digests: impl IntoIterator<Item = &'a Digest>) -> Option<Self> {
+        let digests: Vec<Digest> = digests
+            .into_iter()
+            .filter(|digest| SRI_ALGORITHMS.contains(&digest.algorithm()))
+            .cloned()
+            .collect();
+        (!digests.is_empty()).then_some(Self { digests })
+    }
+
+    /// Every digest listed, in order.
+    #[must_use]
+    pub fn digests(&self) -> &[Digest] {
+        &self.digests
+    }
+
+    /// The strongest algorithm listed, the only one a resource is checked with.
+    #[must_use]
+    pub fn strongest_algorithm(&self) -> HashAlgorithm {
+        self.digests
+            .iter()
+            .map(Digest::algorithm)
+            .max_by_key(|algorithm| strength(*algorithm))
+            .expect("integrity metadata is never empty")
+    }
+
+    /// The digests of the strongest algorithm listed, any of which a resource may match.
+    pub fn strongest(&self) -> impl Iterator<Item = &Digest> {
+        let algorithm = self.strongest_algorithm();
+        self.digests
+            .iter()
+            .filter(move |digest| digest.algorithm() == algorithm)
+    }
+
+    /// Compares against digests computed for a resource, using only the strongest algorithm.
+    ///
+    /// Returns `None` if that algorithm wasn't computed, so no verdict can be given.
+    #[must_use]
+    pub fn compare(&self, hashes: &BTreeMap<HashAlgorithm, Digest>) -> Option<Comparison> {
+        let algorithm = self.strongest_algorithm();
+        let digest = hashes.get(&algorithm)?;
+        Some(if self.strongest().any(|expected| expected == digest) {
+            Comparison::Match(algorithm)
+        } else {
+            Comparison::Mismatch
+        })
+    }
+}
+
+/// How an algorithm ranks when picking the strongest; SHA-512 beats SHA-384 beats SHA-256.
+fn strength(algorithm: HashAlgorithm) -> Option<usize> {
+    SRI_ALGORITHMS
+        .iter()
+        .position(|sri_algorithm| *sri_algorithm == algorithm)
+}
+
+impl fmt::Display for Integrity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, digest) in self.digests.iter().enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}-{}", digest.algorithm().id(), digest.to_base64())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Integrity {
+    type Err = ParseIntegrityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digests: Vec<Digest> = s.split_ascii_whitespace().filter_map(parse_token).collect();
+        // Browsers load a resource whose metadata has no usable digests at all, but that is
+        // never what someone checking a file means.
+        if digests.is_empty() {
+            return Err(ParseIntegrityError(s.trim().to_owned()));
+        }
+        Ok(Self { digests })
+    }
+}
+
+/// Parses one `<algorithm>-<base64>[?<options>]` token, or `None` if it should be ignored.
+fn parse_token(token: &str) -> Option<Digest> {
+    let (algorithm, value) = token.split_once('-')?;
+    let algorithm = SRI_ALGORITHMS
+        .into_iter()
+        .find(|sri_algorithm| sri_algorithm.id() == algorithm)?;
+    let value = value
+        .split_once('?')
+        .map_or(value, |(value, _options)| value);
+    let bytes = [
+        data_encoding::BASE64,
+        data_encoding::BASE64_NOPAD,
+        data_encoding::BASE64URL,
+        data_encoding::BASE64URL_NOPAD,
+    ]
+    .into_iter()
+    .find_map(|encoding| encoding.decode(value.as_bytes()).ok())?;
+    Digest::new(algorithm, bytes).ok()
+}
+
+/// The error returned when integrity metadata has no SHA-256, SHA-384 or SHA-512 digest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseIntegrityError(pub String);
+
+impl fmt::Display for ParseIntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "no sha256, sha384 or sha512 digest in integrity metadata: {:?}",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseIntegrityError {}
+
+/// Hashes the file at `path` and returns its integrity metadata.
+///
+/// There is a digest for each of `algorithms` that Subresource Integrity allows. If there are
+/// none, SHA-384 is used, as recommended for `<script>` tags.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be hashed; see [`crate::hash_file_multi`].
+///
+/// # Examples
+///
+/// ```rust
+/// use hash_checker::{HashAlgorithm, integrity_for_file};
+/// let integrity =
+///     integrity_for_file("examples/valid.txt", &[HashAlgorithm::Sha384]).expect("readable");
+/// assert_eq!(
+///     integrity.to_string(),
+///     "sha384-SOQr6enzDPifQZ6dtwrv+LmoJsz2YINqM6kxPhcCOqsDFT7nv6kPOFmhjonAMBOq"
+/// );
+/// ```
+pub fn integrity_for_file(
+    path: impl AsRef<Path>,
+    algorithms: &[HashAlgorithm],
+) -> Result<Integrity, HashError> {
+    let mut algorithms: Vec<HashAlgorithm> = algorithms
+        .iter()
+        .copied()
+        .filter(|algorithm| SRI_ALGORITHMS.contains(algorithm))
+        .collect();
+    if algorithms.is_empty() {
+        algorithms.push(HashAlgorithm::Sha384);
+    }
+    let hashes = crate::hash_file_multi(path, &algorithms)?;
+    Ok(Integrity::from_digests(hashes.values()).expect("only SRI algorithms were computed"))
+}
+
+/// Hashes the file at `path` with the strongest algorithm in `integrity`, and reports whether
+/// it matched.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be hashed; see [`crate::hash_file_multi`].
+pub fn verify_integrity(
+    path: impl AsRef<Path>,
+    integrity: &Integrity,
+) -> Result<Comparison, HashError> {
+    let hashes = crate::hash_file_multi(path, &[integrity.strongest_algorithm()])?;
+    Ok(integrity
+        .compare(&hashes)
+        .expect("the strongest algorithm was computed"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID_SHA256: &str = "sha256-bXg5KliGF3/luG5YWgtpWivNAaBVBLPE44vI7rIegyY=";
+    const VALID_SHA384: &str =
+        "sha384-SOQr6enzDPifQZ6dtwrv+LmoJsz2YINqM6kxPhcCOqsDFT7nv6kPOFmhjonAMBOq";
+
+    #[test]
+    fn test_parse() {
+        let integrity: Integrity = format!(
+            "  {VALID_SHA256}?ct=application/javascript md5-abc sha384-!!! sha1-{}\n",
+            &VALID_SHA256["sha256-".len()..]
+        )
+        .parse()
+        .expect("one valid token");
+        assert_eq!(integrity.to_string(), VALID_SHA256);
+
+        // URL-safe Base64 and missing padding are accepted, and written back out as standard.
+        let url_safe = VALID_SHA256.replace('/', "_").replace('=', "");
+        let integrity: Integrity = url_safe.parse().expect("valid");
+        assert_eq!(integrity.to_string(), VALID_SHA256);
+
+        for invalid in ["", "sha256-", "sha256-bXg5", "md5-bXg5KliGF3/luG5YWgtpWg=="] {
+            assert!(invalid.parse::<Integrity>().is_err(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn test_strongest_algorithm_wins() {
+        let wrong_sha512 = format!("sha512-{}", data_encoding::BASE64.encode(&[0; 64]));
+        let integrity: Integrity = format!("{VALID_SHA256} {VALID_SHA384}")
+            .parse()
+            .expect("valid");
+        assert_eq!(integrity.strongest_algorithm(), HashAlgorithm::Sha384);
+        assert_eq!(
+            verify_integrity("examples/valid.txt", &integrity).expect("readable"),
+            Comparison::Match(HashAlgorithm::Sha384)
+        );
+
+        // A correct weaker digest doesn't rescue a wrong stronger one.
+        let integrity: Integrity = format!("{VALID_SHA384} {wrong_sha512}")
+            .parse()
+            .expect("valid");
+        assert_eq!(
+            verify_integrity("examples/valid.txt", &integrity).expect("readable"),
+            Comparison::Mismatch
+        );
+
+        // Any digest of the strongest algorithm may match, e.g. while a file is being replaced.
+        let wrong_sha384 = format!("sha384-{}", data_encoding::BASE64.encode(&[0; 48]));
+        let integrity: Integrity = format!("{wrong_sha384} {VALID_SHA384}")
+            .parse()
+            .expect("valid");
+        assert_eq!(
+            verify_integrity("examples/valid.txt", &integrity).expect("readable"),
+            Comparison::Match(HashAlgorithm::Sha384)
+        );
+    }
+
+    #[test]
+    fn test_integrity_for_file() {
+        let integrity = integrity_for_file(
+            "examples/valid.txt",
+            &[HashAlgorithm::Sha256, HashAlgorithm::Sha384],
+        )
+        .expect("readable");
+        assert_eq!(
+            integrity.to_string(),
+            format!("{VALID_SHA256} {VALID_SHA384}")
+        );
+    }
+}
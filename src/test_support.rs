@@ -0,0 +1,31 @@
+//! Fixtures shared by the unit tests of the library and the command-line interface
+
+use std::fs;
+use std::path::PathBuf;
+
+/// A fresh directory under the system temp directory, removed when dropped.
+pub(crate) struct TempDir(pub(crate) PathBuf);
+
+impl TempDir {
+    /// Creates an empty directory for the test called `name`.
+    pub(crate) fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("hash-checker-{name}-{}", std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).expect("Failed to create a temporary directory");
+        Self(dir)
+    }
+
+    /// Writes a file at `path` inside the directory, creating its parents.
+    pub(crate) fn write(&self, path: &str, contents: &str) {
+        let path = self.0.join(path);
+        fs::create_dir_all(path.parent().expect("has a parent"))
+            .expect("Failed to create a directory");
+        fs::write(path, contents).expect("Failed to write a file");
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.0).ok();
+    }
+}
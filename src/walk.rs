@@ -0,0 +1,470 @@
+//! Recursively listing and hashing the files under a directory
+
+use crate::batch::parallel_map;
+use crate::{BatchOptions, Digest, HashAlgorithm, HashError, Ordered};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The name of the ignore file honoured by default, which uses `.gitignore` syntax.
+pub const HASH_IGNORE_FILE: &str = ".hashignore";
+
+/// Which files [`walk`] yields and how it treats symbolic links.
+///
+/// Patterns use `.gitignore` glob syntax and are matched against paths relative to the root of
+/// the walk, so `*.iso` matches at any depth while `/dist/*.iso` only matches directly inside
+/// `dist`.
+///
+/// # Examples
+///
+/// ```rust
+/// use hash_checker::WalkOptions;
+/// let options = WalkOptions::new()
+///     .include("*.tar.gz")
+///     .exclude("nightly/")
+///     .ignore_file(".gitignore")
+///     .follow_symlinks(true);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WalkOptions {
+    include: Vec<String>,
+    exclude: Vec<String>,
+    ignore_files: Vec<String>,
+    follow_symlinks: bool,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            ignore_files: vec![HASH_IGNORE_FILE.to_owned()],
+            follow_symlinks: false,
+        }
+    }
+}
+
+impl WalkOptions {
+    /// Yields every file, honouring only [`HASH_IGNORE_FILE`]s and not following symbolic links.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only yields files matching `pattern`, or any other included pattern.
+    #[must_use]
+    pub fn include(mut self, pattern: impl Into<String>) -> Self {
+        self.include.push(pattern.into());
+        self
+    }
+
+    /// Skips files and directories matching `pattern`.
+    #[must_use]
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude.push(pattern.into());
+        self
+    }
+
+    /// Also honours ignore files called `name`, such as `.gitignore`, in every directory walked.
+    #[must_use]
+    pub fn ignore_file(mut self, name: impl Into<String>) -> Self {
+        self.ignore_files.push(name.into());
+        self
+    }
+
+    /// Whether to descend into symbolic links to directories and yield links to files.
+    ///
+    /// Links that lead back to one of their own ancestors are reported as
+    /// [`WalkError::SymlinkLoop`] rather than followed forever.
+    #[must_use]
+    pub fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    fn overrides(&self, root: &Path) -> Result<ignore::overrides::Override, WalkError> {
+        let mut overrides = ignore::overrides::OverrideBuilder::new(root);
+        let patterns = self
+            .include
+            .iter()
+            .map(|pattern| (pattern, pattern.clone()))
+            .chain(
+                self.exclude
+                    .iter()
+                    .map(|pattern| (pattern, format!("!{pattern}"))),
+            );
+        for (pattern, glob) in patterns {
+            overrides.add(&glob).map_err(|err| WalkError::Pattern {
+                pattern: pattern.clone(),
+                message: err.to_string(),
+            })?;
+        }
+        overrides.build().map_err(|err| WalkError::Pattern {
+            pattern: String::new(),
+            message: err.to_string(),
+        })
+    }
+
+    fn build(&self, root: &Path) -> Result<ignore::Walk, WalkError> {
+        let overrides = self.overrides(root)?;
+        let mut builder = ignore::WalkBuilder::new(root);
+        builder
+            .standard_filters(false)
+            .overrides(overrides)
+            .follow_links(self.follow_symlinks)
+            .sort_by_file_name(|a, b| a.cmp(b));
+        for name in &self.ignore_files {
+            builder.add_custom_ignore_filename(name);
+        }
+        Ok(builder.build())
+    }
+}
+
+/// An error encountered while walking a directory.
+#[derive(Debug)]
+pub enum WalkError {
+    /// An include or exclude pattern is not a valid glob.
+    Pattern { pattern: String, message: String },
+
+    /// A symbolic link points at one of its own ancestors.
+    SymlinkLoop { path: PathBuf, ancestor: PathBuf },
+
+    /// A directory or ignore file could not be read.
+    Io {
+        path: Option<PathBuf>,
+        source: io::Error,
+    },
+
+    /// Any other problem reported while walking, such as a malformed ignore file.
+    Other {
+        path: Option<PathBuf>,
+        message: String,
+    },
+
+    /// A file was found but could not be hashed.
+    Hash(HashError),
+}
+
+impl WalkError {
+    fn new(err: ignore::Error, path: Option<PathBuf>) -> Self {
+        match err {
+            ignore::Error::WithPath { path, err } => Self::new(*err, Some(path)),
+            ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+                Self::new(*err, path)
+            }
+            ignore::Error::Loop { ancestor, child } => Self::SymlinkLoop {
+                path: child,
+                ancestor,
+            },
+            ignore::Error::Io(source) => Self::Io { path, source },
+            err => Self::Other {
+                path,
+                message: err.to_string(),
+            },
+        }
+    }
+
+    /// The path the error is about, if any.
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Pattern { .. } => None,
+            Self::SymlinkLoop { path, .. } => Some(path),
+            Self::Io { path, .. } | Self::Other { path, .. } => path.as_deref(),
+            Self::Hash(err) => err.path(),
+        }
+    }
+}
+
+impl fmt::Display for WalkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pattern { pattern, message } => {
+                write!(f, "invalid pattern {pattern:?}: {message}")
+            }
+            Self::SymlinkLoop { path, ancestor } => write!(
+                f,
+                "{}: symbolic link loops back to {}",
+                path.display(),
+                ancestor.display()
+            ),
+            Self::Io {
+                path: Some(path),
+                source,
+            } => write!(f, "{}: {source}", path.display()),
+            Self::Io { path: None, source } => source.fmt(f),
+            Self::Other {
+                path: Some(path),
+                message,
+            } => write!(f, "{}: {message}", path.display()),
+            Self::Other {
+                path: None,
+                message,
+            } => f.write_str(message),
+            Self::Hash(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for WalkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Hash(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<HashError> for WalkError {
+    fn from(err: HashError) -> Self {
+        Self::Hash(err)
+    }
+}
+
+/// Lists every file under `root`, recursively, sorted by path.
+///
+/// If `root` is a file rather than a directory, just that file is yielded.
+///
+/// # Arguments
+///
+/// * `root` - The directory to walk.
+/// * `options` - Which files to yield and whether to follow symbolic links.
+///
+/// # Returns
+///
+/// An iterator over the path of every file found, or the error encountered in its place.
+///
+/// # Errors
+///
+/// Returns an error up front if one of the include or exclude patterns is invalid.
+///
+/// # Examples
+///
+/// ```rust
+/// use hash_checker::{WalkOptions, walk};
+/// let files: Vec<_> = walk("examples", &WalkOptions::new().include("*.txt"))
+///     .expect("valid patterns")
+///     .collect::<Result<_, _>>()
+///     .expect("readable directory");
+/// assert_eq!(files, [std::path::Path::new("examples/valid.txt")]);
+/// ```
+pub fn walk<P: AsRef<Path>>(
+    root: P,
+    options: &WalkOptions,
+) -> Result<impl Iterator<Item = Result<PathBuf, WalkError>> + use<P>, WalkError> {
+    let walk = options.build(root.as_ref())?;
+    Ok(walk.filter_map(|entry| match entry {
+        Ok(entry) => entry
+            .file_type()
+            .is_some_and(|kind| kind.is_file())
+            .then(|| Ok(entry.into_path())),
+        Err(err) => Some(Err(WalkError::new(err, None))),
+    }))
+}
+
+/// A file found by [`hash_paths`] and its digests.
+type HashedFile = (PathBuf, BTreeMap<HashAlgorithm, Digest>);
+
+/// Hashes each of `paths` with every one of `algorithms`, walking directories with `options`
+/// and hashing several files at once as configured by `batch`.
+///
+/// # Arguments
+///
+/// * `paths` - Files to hash and directories to walk.
+/// * `algorithms` - The algorithms to hash every file with.
+/// * `options` - Which files to hash inside directories and whether to follow symbolic links.
+/// * `batch` - How many files to hash at once.
+///
+/// # Returns
+///
+/// An iterator over every file and its digests, or the error encountered in its place, in the
+/// order the paths were given and each directory was walked.
+///
+/// # Errors
+///
+/// Returns an error up front if one of the include or exclude patterns is invalid.
+///
+/// # Examples
+///
+/// ```rust
+/// use hash_checker::{BatchOptions, HashAlgorithm, WalkOptions, hash_paths};
+/// let results = hash_paths(
+///     vec!["examples".into()],
+///     &[HashAlgorithm::Sha256],
+///     &WalkOptions::new(),
+///     &BatchOptions::new().workers(4),
+/// )
+/// .expect("valid patterns");
+/// for result in results {
+///     match result {
+///         Ok((path, hashes)) => println!("{}: {:?}", path.display(), hashes),
+///         Err(err) => eprintln!("{err}"),
+///     }
+/// }
+/// ```
+pub fn hash_paths(
+    paths: Vec<PathBuf>,
+    algorithms: &[HashAlgorithm],
+    options: &WalkOptions,
+    batch: &BatchOptions,
+) -> Result<Ordered<Result<HashedFile, WalkError>>, WalkError> {
+    options.overrides(Path::new(""))?;
+    let options = options.clone();
+    let files = paths.into_iter().flat_map(move |path| {
+        let files: Box<dyn Iterator<Item = Result<PathBuf, WalkError>> + Send> = if path.is_dir() {
+            match walk(path, &options) {
+                Ok(files) => Box::new(files),
+                Err(err) => Box::new(std::iter::once(Err(err))),
+            }
+        } else {
+            Box::new(std::iter::once(Ok(path)))
+        };
+        files
+    });
+
+    let algorithms = algorithms.to_vec();
+    let hash_batch = *batch;
+    let hashed = parallel_map(files, batch, move |file, token| {
+        let path = file?;
+        let hashes = crate::hash_file_with(&path, &algorithms, hash_batch.hash_options(token))?;
+        Ok((path, hashes))
+    });
+    Ok(hashed.ordered())
+}
+
+/// Hashes every file under `root` with every one of `algorithms`, in the order [`walk`] finds
+/// them. See [`hash_paths`].
+///
+/// # Errors
+///
+/// Returns an error up front if one of the include or exclude patterns is invalid. Problems
+/// with individual files and directories are yielded in their place.
+pub fn hash_directory(
+    root: impl AsRef<Path>,
+    algorithms: &[HashAlgorithm],
+    options: &WalkOptions,
+    batch: &BatchOptions,
+) -> Result<Ordered<Result<HashedFile, WalkError>>, WalkError> {
+    hash_paths(vec![root.as_ref().to_owned()], algorithms, options, batch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    /// The files found under `dir`, relative to it and with `/` separators.
+    fn walked(dir: &TempDir, options: &WalkOptions) -> Vec<String> {
+        walk(&dir.0, options)
+            .expect("Expected valid patterns.")
+            .map(|path| {
+                let path = path.expect("Expected every file to be readable.");
+                let relative = path.strip_prefix(&dir.0).expect("under the root");
+                relative.to_string_lossy().replace('\\', "/")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_walk_order_and_patterns() {
+        let dir = TempDir::new("walk");
+        for path in [
+            "b.txt",
+            "a.iso",
+            "sub/c.iso",
+            "sub/d.log",
+            ".hidden",
+            "logs/e.log",
+        ] {
+            dir.write(path, path);
+        }
+
+        assert_eq!(
+            walked(&dir, &WalkOptions::new()),
+            [
+                ".hidden",
+                "a.iso",
+                "b.txt",
+                "logs/e.log",
+                "sub/c.iso",
+                "sub/d.log"
+            ]
+        );
+        assert_eq!(
+            walked(&dir, &WalkOptions::new().include("*.iso")),
+            ["a.iso", "sub/c.iso"]
+        );
+        assert_eq!(
+            walked(&dir, &WalkOptions::new().exclude("*.log").exclude("/sub/")),
+            [".hidden", "a.iso", "b.txt"]
+        );
+    }
+
+    #[test]
+    fn test_ignore_files() {
+        let dir = TempDir::new("ignore");
+        dir.write(".hashignore", "*.tmp\n");
+        dir.write(".gitignore", "build/\n");
+        dir.write("keep.txt", "");
+        dir.write("skip.tmp", "");
+        dir.write("build/out.bin", "");
+
+        assert_eq!(
+            walked(&dir, &WalkOptions::new()),
+            [".gitignore", ".hashignore", "build/out.bin", "keep.txt"]
+        );
+        assert_eq!(
+            walked(&dir, &WalkOptions::new().ignore_file(".gitignore")),
+            [".gitignore", ".hashignore", "keep.txt"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_loop() {
+        let dir = TempDir::new("symlinks");
+        dir.write("sub/file.txt", "");
+        std::os::unix::fs::symlink(&dir.0, dir.0.join("sub/loop"))
+            .expect("Failed to create a symlink");
+
+        assert_eq!(walked(&dir, &WalkOptions::new()), ["sub/file.txt"]);
+
+        let results: Vec<_> = walk(&dir.0, &WalkOptions::new().follow_symlinks(true))
+            .expect("Expected valid patterns.")
+            .collect();
+        assert!(
+            results
+                .iter()
+                .any(|result| matches!(result, Err(WalkError::SymlinkLoop { .. }))),
+            "{results:?}"
+        );
+    }
+
+    #[test]
+    fn test_hash_directory() {
+        let results: Vec<_> = hash_directory(
+            "examples",
+            &[HashAlgorithm::Sha256],
+            &WalkOptions::new().include("valid.txt"),
+            &BatchOptions::new(),
+        )
+        .expect("Expected valid patterns.")
+        .collect::<Result<_, _>>()
+        .expect("Expected every file to hash.");
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].1[&HashAlgorithm::Sha256].to_string(),
+            "6d78392a5886177fe5b86e585a0b695a2bcd01a05504b3c4e38bc8eeb21e8326"
+        );
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        assert!(matches!(
+            walk("examples", &WalkOptions::new().include("a[")),
+            Err(WalkError::Pattern { .. })
+        ));
+    }
+}
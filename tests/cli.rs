@@ -0,0 +1,108 @@
+//! The exit status of the command-line interface, which scripts rely on
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const VALID_SHA256: &str = "6d78392a5886177fe5b86e585a0b695a2bcd01a05504b3c4e38bc8eeb21e8326";
+
+/// Runs the app with `args` and returns its exit code.
+fn exit_code(args: &[&str]) -> i32 {
+    let output = Command::new(env!("CARGO_BIN_EXE_hash_checker"))
+        .args(args)
+        .output()
+        .expect("Failed to run hash_checker");
+    output.status.code().expect("hash_checker was killed")
+}
+
+#[test]
+fn test_match_exits_with_0() {
+    assert_eq!(
+        exit_code(&["hash", "examples/valid.txt", "--expected", VALID_SHA256]),
+        0
+    );
+    assert_eq!(exit_code(&["hash", "--quiet", "examples/valid.txt"]), 0);
+}
+
+#[test]
+fn test_mismatch_exits_with_1() {
+    let wrong = "0".repeat(64);
+    assert_eq!(
+        exit_code(&["hash", "examples/valid.txt", "--expected", &wrong]),
+        1
+    );
+    // A checksum file with a wrong digest and a missing file.
+    assert_eq!(exit_code(&["check", "--quiet", "examples/release.sfv"]), 1);
+}
+
+#[test]
+fn test_error_exits_with_2() {
+    assert_eq!(exit_code(&["hash", "examples/missing.txt"]), 2);
+    assert_eq!(exit_code(&["check", "examples/missing.sfv"]), 2);
+    assert_eq!(
+        exit_code(&["hash", "--algo", "sha0", "examples/valid.txt"]),
+        2
+    );
+    assert_eq!(exit_code(&["frobnicate"]), 2);
+}
+
+#[test]
+fn test_several_algorithms_print_bsd_tags() {
+    let output = Command::new(env!("CARGO_BIN_EXE_hash_checker"))
+        .args([
+            "hash",
+            "--algo",
+            "sha256",
+            "--algo",
+            "md5",
+            "examples/valid.txt",
+        ])
+        .output()
+        .expect("Failed to run hash_checker");
+    assert!(output.status.success());
+    // As `sha256sum --tag` writes them, and as `manifest --format bsd` does.
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!(
+            "MD5 (examples/valid.txt) = b2cfa4183267af678ea06c7407d4d6d8\n\
+             SHA256 (examples/valid.txt) = {VALID_SHA256}\n"
+        )
+    );
+}
+
+/// Runs the app with `args` in `dir`, and checks that it succeeded.
+fn run_in(dir: &Path, args: &[&str]) {
+    let status = Command::new(env!("CARGO_BIN_EXE_hash_checker"))
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .expect("Failed to run hash_checker");
+    assert!(status.success(), "{args:?} failed with {status}");
+}
+
+#[test]
+fn test_manifest_leaves_out_absolute_output() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("manifest-absolute-output");
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(dir.join("dist")).expect("Failed to create a temporary directory");
+    fs::write(dir.join("dist/a.txt"), "a\n").expect("Failed to write a file");
+    let expected = "87428fc522803d31065e7bce3cf03fe475096631e5e07bbd7a0fde60c4cf25c7  a.txt\n";
+
+    let output = dir.join("dist/SHA256SUMS");
+    let output = output.to_str().expect("a UTF-8 path");
+    let dist = dir.join("dist");
+    // The first run leaves a manifest in the tree for the later ones to skip.
+    for (cwd, args) in [
+        (&dist, ["manifest", ".", "-o", output].as_slice()),
+        (&dist, ["manifest", ".", "-o", output].as_slice()),
+        (
+            &dir,
+            ["manifest", "--relative-to", "dist", ".", "-o", output].as_slice(),
+        ),
+    ] {
+        run_in(cwd, args);
+        let contents = fs::read_to_string(output).expect("Failed to read the manifest");
+        assert_eq!(contents, expected, "{args:?}");
+    }
+    fs::remove_dir_all(&dir).ok();
+}